@@ -0,0 +1,27 @@
+use std::{env, fs, process::Command};
+
+/// Embed the commit being built as `GIT_COMMIT`, reported by the health routes
+fn main() {
+    // Builds outside a checkout, e.g. in a container, can pass it explicitly
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Some(reference) = fs::read_to_string(".git/HEAD")
+        .ok()
+        .and_then(|head| head.strip_prefix("ref: ").map(|name| name.trim().to_string()))
+    {
+        println!("cargo:rerun-if-changed=.git/{}", reference);
+    }
+
+    let commit = env::var("GIT_COMMIT")
+        .ok()
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short=12", "HEAD"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_COMMIT={}", commit);
+}
@@ -0,0 +1,313 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::Span;
+use crate::{
+    config::AuthConfig,
+    error::{AppError, ErrorCode},
+    state::AppState,
+};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Permission an API key grants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "keys:generate")]
+    KeysGenerate,
+    #[serde(rename = "sign")]
+    Sign,
+    #[serde(rename = "build")]
+    Build,
+    #[serde(rename = "read")]
+    Read,
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Scope::KeysGenerate => "keys:generate",
+            Scope::Sign => "sign",
+            Scope::Build => "build",
+            Scope::Read => "read",
+        })
+    }
+}
+
+/// An API key as stored in configuration; only its digest is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    /// Name reported in logs and used for per-key rate limits and quotas
+    pub id: String,
+    /// Hex SHA-256 digest of the key, e.g. from `printf %s "$KEY" | sha256sum`
+    pub sha256: String,
+    pub scopes: Vec<Scope>,
+    /// Route templates the key may call, such as `/message/sign` or `/calc/*`; all when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<String>,
+}
+
+impl ApiKeyConfig {
+    fn allows_route(&self, route: &str) -> bool {
+        self.routes.is_empty() || self.routes.iter().any(|pattern| route_matches(pattern, route))
+    }
+}
+
+/// Contents of `auth.keys_file`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyFile {
+    #[serde(default)]
+    pub keys: Vec<ApiKeyConfig>,
+}
+
+/// Configured keys, indexed by digest
+#[derive(Clone)]
+pub struct ApiKeys {
+    by_digest: Arc<HashMap<String, ApiKeyConfig>>,
+}
+
+impl ApiKeys {
+    /// `None` when authentication is disabled
+    pub fn new(config: &AuthConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+
+        let by_digest = config
+            .keys
+            .iter()
+            .chain(&config.file_keys)
+            .map(|key| (key.sha256.to_ascii_lowercase(), key.clone()))
+            .collect();
+
+        Some(Self {
+            by_digest: Arc::new(by_digest),
+        })
+    }
+
+    fn find(&self, key: &str) -> Option<&ApiKeyConfig> {
+        self.by_digest.get(&hash_key(key))
+    }
+}
+
+/// The key a request was authenticated with, available as a request extension
+#[derive(Debug, Clone)]
+pub struct AuthenticatedKey {
+    pub id: String,
+}
+
+/// Match a route template against a pattern such as `/calc/rent` or `/calc/*`
+pub fn route_matches(pattern: &str, route: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => route.starts_with(prefix),
+        None => pattern == route,
+    }
+}
+
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Scope each route requires; `None` for routes open to everyone
+fn required_scope(route: &str) -> Option<Scope> {
+    match route {
+        "/health" | "/health/live" | "/health/ready" | "/metrics" => None,
+        route if route == "/openapi.json" || route.starts_with("/docs") => None,
+        // Each call is authenticated against its own route when dispatched
+        "/rpc" | "/batch" => None,
+        "/keypair" | "/keypair/secp256k1" => Some(Scope::KeysGenerate),
+        // These accept secret keys and sign with them
+        "/message/sign" | "/instruction/ed25519" | "/instruction/secp256k1" => Some(Scope::Sign),
+        "/token/create" | "/token/mint" | "/send/sol" | "/send/token" | "/transaction/build"
+        | "/transaction/send" | "/transaction/simulate" | "/simulate/local" => Some(Scope::Build),
+        _ => Some(Scope::Read),
+    }
+}
+
+/// Reject requests without a key that grants the route's scope
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(keys) = &state.api_keys else {
+        return Ok(next.run(req).await);
+    };
+    // Unmatched paths fall through to the 404 fallback
+    let Some(route) = req.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string())
+    else {
+        return Ok(next.run(req).await);
+    };
+    let Some(scope) = required_scope(&route) else {
+        return Ok(next.run(req).await);
+    };
+
+    let key = presented_key(req.headers()).ok_or_else(|| {
+        AppError::request(
+            ErrorCode::Unauthorized,
+            "Missing API key: send `Authorization: Bearer <key>` or `X-Api-Key: <key>`",
+        )
+    })?;
+    let key = keys
+        .find(key)
+        .ok_or_else(|| AppError::request(ErrorCode::Unauthorized, "Invalid API key"))?;
+
+    if !key.scopes.contains(&scope) {
+        return Err(AppError::request(
+            ErrorCode::Forbidden,
+            format!("API key {} lacks the {} scope required by {}", key.id, scope, route),
+        ));
+    }
+    if !key.allows_route(&route) {
+        return Err(AppError::request(
+            ErrorCode::Forbidden,
+            format!("API key {} is not allowed to call {}", key.id, route),
+        ));
+    }
+
+    Span::current().record("api_key", key.id.as_str());
+    req.extensions_mut().insert(AuthenticatedKey { id: key.id.clone() });
+
+    Ok(next.run(req).await)
+}
+
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let header = headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    bearer.or(header).map(str::trim).filter(|key| !key.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use crate::{
+        config::Config,
+        testing::{self, TestResponse},
+    };
+    use super::*;
+
+    const READ_KEY: &str = "read-key";
+    const SIGN_KEY: &str = "sign-key";
+
+    fn app() -> axum::Router {
+        let key = |id: &str, key: &str, scopes: Vec<Scope>, routes: &[&str]| ApiKeyConfig {
+            id: id.to_string(),
+            sha256: hash_key(key),
+            scopes,
+            routes: routes.iter().map(|route| route.to_string()).collect(),
+        };
+        let mut config = Config::default();
+        config.auth.enabled = true;
+        config.auth.keys = vec![
+            key("calc", READ_KEY, vec![Scope::Read], &["/calc/*"]),
+            key("signer", SIGN_KEY, vec![Scope::Sign], &[]),
+        ];
+        testing::app(config)
+    }
+
+    async fn rent(app: &axum::Router, key: Option<(&'static str, &str)>) -> TestResponse {
+        let mut req = testing::post("/calc/rent", json!({ "data_len": 0 }));
+        if let Some((name, value)) = key {
+            req.headers_mut().insert(name, value.parse().unwrap());
+        }
+        testing::call(app, req).await
+    }
+
+    #[tokio::test]
+    async fn missing_and_unknown_keys_are_unauthorized() {
+        let app = app();
+        for key in [None, Some(("x-api-key", "nope")), Some(("authorization", "Bearer nope"))] {
+            let response = rent(&app, key).await;
+            assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+            assert_eq!(response.body["code"], "UNAUTHORIZED");
+            assert_eq!(response.body["success"], false);
+            assert_eq!(response.header("www-authenticate"), Some("Bearer"));
+        }
+    }
+
+    #[tokio::test]
+    async fn keys_are_accepted_in_either_header() {
+        let app = app();
+        let bearer = format!("Bearer {}", READ_KEY);
+        for key in [("x-api-key", READ_KEY), ("authorization", bearer.as_str())] {
+            assert_eq!(rent(&app, Some(key)).await.status, StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn keys_need_the_scope_and_an_allowed_route() {
+        let app = app();
+        let response = rent(&app, Some(("x-api-key", SIGN_KEY))).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+        assert_eq!(response.body["code"], "FORBIDDEN");
+
+        // Same scope, but outside the key's route allowlist
+        let mut req = testing::post("/decode/account", json!({}));
+        req.headers_mut().insert(API_KEY_HEADER, READ_KEY.parse().unwrap());
+        let response = testing::call(&app, req).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+        assert!(response.body["error"].as_str().unwrap().contains("not allowed"));
+    }
+
+    #[tokio::test]
+    async fn health_stays_open() {
+        let response = testing::call(&app(), testing::get("/health/live")).await;
+        assert_eq!(response.status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn dispatched_calls_are_authorized_per_route() {
+        let app = app();
+        let sign = json!({ "message": "hi", "secret": "x" });
+
+        let mut req = testing::post(
+            "/rpc",
+            json!([
+                { "jsonrpc": "2.0", "id": 1, "method": "calc_rent", "params": { "data_len": 0 } },
+                { "jsonrpc": "2.0", "id": 2, "method": "sign_message", "params": sign },
+            ]),
+        );
+        req.headers_mut().insert(API_KEY_HEADER, READ_KEY.parse().unwrap());
+        let response = testing::call(&app, req).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body[0]["result"]["lamports"].is_u64());
+        assert_eq!(response.body[1]["error"]["data"]["status"], 403);
+        assert_eq!(response.body[1]["error"]["data"]["code"], "FORBIDDEN");
+
+        let mut req = testing::post(
+            "/batch",
+            json!({ "operations": [
+                { "route": "/calc/rent", "body": { "data_len": 0 } },
+                { "route": "/message/sign", "body": sign },
+            ] }),
+        );
+        req.headers_mut().insert(API_KEY_HEADER, READ_KEY.parse().unwrap());
+        let response = testing::call(&app, req).await;
+        let results = &response.body["data"]["results"];
+        assert_eq!(results[0]["status"], 200);
+        assert_eq!(results[1]["status"], 403);
+
+        // Without a key, every dispatched call is rejected on its own
+        let req = testing::post(
+            "/rpc",
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "calc_rent", "params": {} }),
+        );
+        let response = testing::call(&app, req).await;
+        assert_eq!(response.body["error"]["data"]["status"], 401);
+    }
+}
@@ -0,0 +1,158 @@
+//! Mock Solana JSON-RPC server that replays fixture responses.
+//!
+//! Fixtures are read from `MOCK_RPC_FIXTURES` (default `fixtures/rpc`), one
+//! `<method>.json` file per RPC method. A file holds a single case or an array
+//! of cases, where a case is:
+//!
+//! ```json
+//! { "params": [...], "result": ... }
+//! { "params": [...], "error": { "code": -32002, "message": "..." } }
+//! { "params": [...], "sequence": [ { "result": ... }, { "result": ... } ] }
+//! ```
+//!
+//! `params` is optional and matches when every value it contains is also
+//! present in the request. The first matching case wins. A `sequence` returns
+//! its responses in order on successive calls, repeating the last one.
+//!
+//! The server listens on `MOCK_RPC_PORT` (default 8899, the `localnet` port), so
+//! requests made with `cluster=localnet` are answered by the mock.
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    env, fs,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Default)]
+struct Fixtures {
+    cases: HashMap<String, Vec<Value>>,
+    cursors: Mutex<HashMap<(String, usize), usize>>,
+}
+
+impl Fixtures {
+    fn load(dir: &Path) -> std::io::Result<Self> {
+        let mut cases = HashMap::new();
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(method) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            let contents = fs::read_to_string(&path)?;
+            let value: Value = serde_json::from_str(&contents).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), e),
+                )
+            })?;
+            let method_cases = match value {
+                Value::Array(items) => items,
+                other => vec![other],
+            };
+            cases.insert(method.to_string(), method_cases);
+        }
+
+        Ok(Self {
+            cases,
+            cursors: Mutex::default(),
+        })
+    }
+
+    fn respond(&self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        let matched = self.cases.get(method).and_then(|cases| {
+            cases.iter().enumerate().find(|(_, case)| {
+                case.get("params")
+                    .is_none_or(|expected| contains(&params, expected))
+            })
+        });
+
+        let Some((index, case)) = matched else {
+            return json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("Method not found: {}", method) },
+            });
+        };
+
+        let case = match case.get("sequence").and_then(Value::as_array) {
+            Some(sequence) if !sequence.is_empty() => {
+                let mut cursors = self.cursors.lock().unwrap();
+                let cursor = cursors.entry((method.to_string(), index)).or_insert(0);
+                let step = &sequence[(*cursor).min(sequence.len() - 1)];
+                *cursor += 1;
+                step
+            }
+            _ => case,
+        };
+
+        let mut response = Map::new();
+        response.insert("jsonrpc".to_string(), json!("2.0"));
+        response.insert("id".to_string(), id);
+        match case.get("error") {
+            Some(error) => response.insert("error".to_string(), error.clone()),
+            None => response.insert(
+                "result".to_string(),
+                case.get("result").cloned().unwrap_or(Value::Null),
+            ),
+        };
+        Value::Object(response)
+    }
+}
+
+/// Whether `actual` contains everything in `expected`
+fn contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|a| contains(a, value))),
+        (Value::Array(actual), Value::Array(expected)) => {
+            expected.len() <= actual.len()
+                && expected.iter().zip(actual).all(|(e, a)| contains(a, e))
+        }
+        _ => actual == expected,
+    }
+}
+
+async fn handle(State(fixtures): State<Arc<Fixtures>>, Json(body): Json<Value>) -> Json<Value> {
+    match body {
+        Value::Array(requests) => Json(Value::Array(
+            requests.iter().map(|request| fixtures.respond(request)).collect(),
+        )),
+        request => Json(fixtures.respond(&request)),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let dir = env::var("MOCK_RPC_FIXTURES").unwrap_or_else(|_| "fixtures/rpc".to_string());
+    let port = env::var("MOCK_RPC_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(8899);
+
+    let fixtures = Fixtures::load(Path::new(&dir))
+        .unwrap_or_else(|e| panic!("failed to load fixtures from {}: {}", dir, e));
+    println!("Loaded fixtures for {} RPC methods from {}", fixtures.cases.len(), dir);
+
+    let app = Router::new()
+        .route("/", post(handle))
+        .with_state(Arc::new(fixtures));
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    println!("Mock RPC listening on {}", addr);
+
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
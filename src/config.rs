@@ -1,3 +1,1106 @@
-pub const MAX_SAFE_INTEGER: u64 = 9_007_199_254_740_991; 
+use std::{
+    collections::HashSet,
+    fs,
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+};
+use axum::http::HeaderValue;
+use clap::{builder::BoolishValueParser, Parser, ValueEnum};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+use crate::{
+    auth::{route_matches, ApiKeyConfig, ApiKeyFile},
+    rpc::Cluster,
+};
 
-pub const DEFAULT_PORT: u16 = 8080;
\ No newline at end of file
+pub const MAX_SAFE_INTEGER: u64 = 9_007_199_254_740_991;
+
+pub const DEFAULT_PORT: u16 = 8080;
+
+pub const DEFAULT_BIND_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+pub const DEFAULT_BODY_LIMIT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Owner read and write only
+pub const DEFAULT_UNIX_SOCKET_MODE: u32 = 0o600;
+
+pub const SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+pub const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+pub const MAX_CONCURRENT_REQUESTS: usize = 1_024;
+
+pub const HEALTH_CHECK_TIMEOUT_SECS: u64 = 2;
+
+pub const MAX_VERIFY_BATCH_SIZE: usize = 10_000;
+
+pub const MAX_DISPATCH_BATCH_SIZE: usize = 1_000;
+
+pub const DISPATCH_CONCURRENCY: usize = 16;
+
+pub const RPC_TIMEOUT_SECS: u64 = 30;
+
+pub const CONFIRMATION_POLL_INTERVAL_MS: u64 = 500;
+
+pub const CONFIRMATION_TIMEOUT_SECS: u64 = 90;
+
+/// Time routes that wait for confirmation get beyond `rpc.confirmation_timeout_secs`
+pub const CONFIRMATION_TIMEOUT_MARGIN_SECS: u64 = 30;
+
+/// Routes that may wait for a submitted transaction to confirm
+pub const CONFIRMING_ROUTES: &[&str] = &["/transaction/send", "/rpc"];
+
+pub const MAX_SIMULATION_ACCOUNTS: usize = 100;
+
+/// Total account data `/simulate/local` may seed its bank with
+pub const MAX_SIMULATION_DATA_BYTES: usize = 10 * 1024 * 1024;
+
+pub const BLOCKHASH_CACHE_TTL_MS: u64 = 2_000;
+
+pub const DEFAULT_LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+pub const SOL_DECIMALS: u8 = 9;
+
+pub const TLS_RELOAD_CHECK_INTERVAL_SECS: u64 = 10;
+
+pub const TLS_HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+
+pub const METRICS_UPKEEP_INTERVAL_SECS: u64 = 5;
+
+pub const QUOTA_FLUSH_INTERVAL_SECS: u64 = 10;
+
+pub const LATENCY_BUCKETS_SECS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Solana HTTP API server
+///
+/// Every setting can come from the `--config` file, the environment variable shown below or a
+/// flag; flags take precedence over environment variables, which take precedence over the file.
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    /// TOML configuration file
+    #[arg(long, env = "API_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Print the effective configuration as TOML and exit
+    #[arg(long)]
+    pub print_config: bool,
+
+    #[arg(long, env = "BIND_ADDRESS")]
+    bind_address: Option<IpAddr>,
+
+    #[arg(long, env = "PORT")]
+    port: Option<u16>,
+
+    /// Listen on this Unix domain socket instead of the TCP address. Without TLS, and without
+    /// per-IP rate limits or quotas unless `trusted_proxies` is set.
+    #[arg(long, env = "UNIX_SOCKET")]
+    unix_socket: Option<PathBuf>,
+
+    /// Octal permissions of the Unix domain socket, e.g. 660 to admit the owning group
+    #[arg(long, env = "UNIX_SOCKET_MODE", value_parser = parse_mode)]
+    unix_socket_mode: Option<u32>,
+
+    /// How long in-flight requests may take to finish after SIGTERM or SIGINT
+    #[arg(long, env = "SHUTDOWN_TIMEOUT_SECS")]
+    shutdown_timeout_secs: Option<u64>,
+
+    /// Allowed CORS origins, comma separated, or `*` for any
+    #[arg(long, env = "CORS_ORIGINS", value_delimiter = ',')]
+    cors_origins: Option<Vec<String>>,
+
+    /// Maximum request body size in bytes
+    #[arg(long, env = "BODY_LIMIT_BYTES")]
+    body_limit_bytes: Option<usize>,
+
+    /// Requests still running after this many seconds are answered with 504
+    #[arg(long, env = "REQUEST_TIMEOUT_SECS")]
+    request_timeout_secs: Option<u64>,
+
+    /// Requests handled at once; further requests are shed with 503
+    #[arg(long, env = "MAX_CONCURRENT_REQUESTS")]
+    max_concurrent_requests: Option<usize>,
+
+    /// PEM certificate chain; serves HTTPS together with `--tls-key`
+    #[arg(long, env = "TLS_CERT")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for `--tls-cert`
+    #[arg(long, env = "TLS_KEY")]
+    tls_key: Option<PathBuf>,
+
+    /// PEM CA bundle for client certificates; signing routes then require one
+    #[arg(long, env = "TLS_CLIENT_CA")]
+    tls_client_ca: Option<PathBuf>,
+
+    /// Default RPC endpoint for requests that do not name a cluster
+    #[arg(long, env = "SOLANA_RPC_URL")]
+    rpc_url: Option<String>,
+
+    #[arg(long, env = "RPC_TIMEOUT_SECS")]
+    rpc_timeout_secs: Option<u64>,
+
+    /// How long `/transaction/send` waits for the requested commitment
+    #[arg(long, env = "CONFIRMATION_TIMEOUT_SECS")]
+    confirmation_timeout_secs: Option<u64>,
+
+    /// Serve `/openapi.json` and `/docs`
+    #[arg(long, env = "ENABLE_DOCS", value_parser = BoolishValueParser::new())]
+    docs: Option<bool>,
+
+    /// Serve `/simulate/local`
+    #[arg(long, env = "ENABLE_LOCAL_SIMULATION", value_parser = BoolishValueParser::new())]
+    local_simulation: Option<bool>,
+
+    /// Accept arbitrary http(s) URLs in a request's `cluster` field; lets callers make the
+    /// server send requests to any URL, including internal addresses
+    #[arg(long, env = "ALLOW_CUSTOM_CLUSTER_URLS", value_parser = BoolishValueParser::new())]
+    custom_cluster_urls: Option<bool>,
+
+    /// Serve Prometheus metrics at `/metrics`
+    #[arg(long, env = "ENABLE_METRICS", value_parser = BoolishValueParser::new())]
+    metrics: Option<bool>,
+
+    /// Serve `/metrics` on this port instead of the API port
+    #[arg(long, env = "METRICS_PORT")]
+    metrics_port: Option<u16>,
+
+    /// Require an API key on every route except health, docs and metrics
+    #[arg(long, env = "ENABLE_AUTH", value_parser = BoolishValueParser::new())]
+    auth: Option<bool>,
+
+    /// TOML file with `[[keys]]` entries, in addition to `auth.keys`
+    #[arg(long, env = "API_KEYS_FILE")]
+    api_keys_file: Option<PathBuf>,
+
+    /// Apply per-IP and per-key rate limits and daily quotas
+    #[arg(long, env = "ENABLE_RATE_LIMIT", value_parser = BoolishValueParser::new())]
+    rate_limit: Option<bool>,
+
+    /// JSON file daily quota usage is persisted to across restarts
+    #[arg(long, env = "QUOTA_FILE")]
+    quota_file: Option<PathBuf>,
+
+    #[arg(long, env = "LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// Log filter directives such as `info` or `solana_rust_api=debug,tower_http=warn`
+    #[arg(long, env = "RUST_LOG")]
+    log_filter: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("invalid configuration:\n  {}", .0.join("\n  "))]
+    Invalid(Vec<String>),
+}
+
+/// Effective server configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub tls: TlsConfig,
+    pub limits: LimitsConfig,
+    pub rpc: RpcConfig,
+    pub features: FeatureConfig,
+    pub metrics: MetricsConfig,
+    pub auth: AuthConfig,
+    pub rate_limit: RateLimitConfig,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_address: IpAddr,
+    pub port: u16,
+    /// `["*"]` allows any origin
+    pub cors_origins: Vec<String>,
+    pub body_limit_bytes: usize,
+    /// Listen on this Unix domain socket instead of `bind_address:port`. TLS cannot be used
+    /// on it, and clients have no IP address there, so per-IP rate limits and quotas only
+    /// apply to addresses taken from `X-Forwarded-For` with `rate_limit.trusted_proxies`.
+    pub unix_socket: Option<PathBuf>,
+    /// Permissions set on `unix_socket` once bound
+    pub unix_socket_mode: u32,
+    /// How long in-flight requests may take to finish once shutdown begins
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: DEFAULT_BIND_ADDRESS,
+            port: DEFAULT_PORT,
+            cors_origins: vec!["*".to_string()],
+            body_limit_bytes: DEFAULT_BODY_LIMIT_BYTES,
+            unix_socket: None,
+            unix_socket_mode: DEFAULT_UNIX_SOCKET_MODE,
+            shutdown_timeout_secs: SHUTDOWN_TIMEOUT_SECS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM certificate chain; HTTPS is served when both `cert` and `key` are set
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    /// PEM CA bundle client certificates are verified against; enables mutual TLS
+    pub client_ca: Option<PathBuf>,
+    /// Routes that require a verified client certificate when `client_ca` is set
+    pub client_cert_routes: Vec<String>,
+}
+
+impl TlsConfig {
+    pub fn enabled(&self) -> bool {
+        self.cert.is_some() && self.key.is_some()
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            cert: None,
+            key: None,
+            client_ca: None,
+            client_cert_routes: vec!["/message/sign".to_string(), "/instruction/*".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Requests still running after this long are answered with 504
+    pub request_timeout_secs: u64,
+    /// Requests handled at once across all routes; further requests are shed with 503
+    pub max_concurrent_requests: usize,
+    /// Overrides for groups of routes. For body limits and timeouts the first matching
+    /// rule that sets a value wins; every matching concurrency limit applies.
+    pub routes: Vec<RouteLimitsOverride>,
+}
+
+impl LimitsConfig {
+    /// Timeout set by the first matching rule for a route template
+    fn rule_timeout_secs(&self, route: &str) -> Option<u64> {
+        self.matching(route).find_map(|rule| rule.timeout_secs)
+    }
+
+    /// Body limit for a route template, when it overrides `server.body_limit_bytes`
+    pub fn body_limit_bytes(&self, route: &str) -> Option<usize> {
+        self.matching(route).find_map(|rule| rule.body_limit_bytes)
+    }
+
+    /// Tightest concurrency limit of the rules matching a route template
+    pub fn max_concurrent(&self, route: &str) -> Option<usize> {
+        self.matching(route).filter_map(|rule| rule.max_concurrent).min()
+    }
+
+    fn matching<'a>(&'a self, route: &'a str) -> impl Iterator<Item = &'a RouteLimitsOverride> {
+        self.routes
+            .iter()
+            .filter(move |rule| route_matches(&rule.route, route))
+    }
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout_secs: REQUEST_TIMEOUT_SECS,
+            max_concurrent_requests: MAX_CONCURRENT_REQUESTS,
+            // Routes waiting for confirmation derive their timeout, see `Config::timeout_secs`
+            routes: vec![
+                // CPU-bound: each request runs a local bank or verifies many signatures
+                RouteLimitsOverride {
+                    route: "/simulate/local".to_string(),
+                    max_concurrent: Some(8),
+                    ..RouteLimitsOverride::default()
+                },
+                RouteLimitsOverride {
+                    route: "/message/verify/batch".to_string(),
+                    max_concurrent: Some(16),
+                    ..RouteLimitsOverride::default()
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteLimitsOverride {
+    /// Route template such as `/simulate/local` or `/transaction/*`; a trailing * matches a prefix
+    pub route: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_limit_bytes: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Requests to the matching routes handled at once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    /// Used when a request does not name a cluster; RPC-backed routes fail without one
+    pub url: Option<String>,
+    pub timeout_secs: u64,
+    pub confirmation_timeout_secs: u64,
+    /// Endpoints used when a request names a well-known cluster
+    pub clusters: ClusterUrls,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            timeout_secs: RPC_TIMEOUT_SECS,
+            confirmation_timeout_secs: CONFIRMATION_TIMEOUT_SECS,
+            clusters: ClusterUrls::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClusterUrls {
+    pub mainnet: String,
+    pub devnet: String,
+    pub testnet: String,
+    pub localnet: String,
+}
+
+impl Default for ClusterUrls {
+    fn default() -> Self {
+        Self {
+            mainnet: Cluster::Mainnet.url().to_string(),
+            devnet: Cluster::Devnet.url().to_string(),
+            testnet: Cluster::Testnet.url().to_string(),
+            localnet: Cluster::Localnet.url().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureConfig {
+    pub docs: bool,
+    pub local_simulation: bool,
+    /// Off by default: any http(s) URL in `cluster` makes the server send requests to it,
+    /// including to internal addresses
+    pub custom_cluster_urls: bool,
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        Self {
+            docs: true,
+            local_simulation: true,
+            custom_cluster_urls: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// Admin port for `/metrics`, on the same bind address; the API port is used when unset
+    pub port: Option<u16>,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            port: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub enabled: bool,
+    pub keys_file: Option<PathBuf>,
+    pub keys: Vec<ApiKeyConfig>,
+    /// Keys read from `keys_file` at startup
+    #[serde(skip)]
+    pub file_keys: Vec<ApiKeyConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Reverse proxies in front of the server that append to `X-Forwarded-For`. Clients are
+    /// identified by the address the outermost of them saw, the `trusted_proxies`th entry
+    /// from the right; anything further left is client-supplied. With 0 the header is ignored.
+    pub trusted_proxies: usize,
+    pub per_ip: BucketConfig,
+    /// Applies to authenticated requests, in addition to `per_ip`
+    pub per_key: BucketConfig,
+    /// Stricter limits for expensive routes, applied in addition to the defaults
+    pub routes: Vec<RouteLimitConfig>,
+    /// Requests per UTC day for each IP; unlimited when unset
+    pub daily_quota_per_ip: Option<u64>,
+    /// Requests per UTC day for each API key; unlimited when unset
+    pub daily_quota_per_key: Option<u64>,
+    /// Usage is kept in memory only, and lost on restart, when unset
+    pub quota_file: Option<PathBuf>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trusted_proxies: 0,
+            per_ip: BucketConfig {
+                burst: 60,
+                per_minute: 120,
+            },
+            per_key: BucketConfig {
+                burst: 300,
+                per_minute: 600,
+            },
+            routes: vec![
+                RouteLimitConfig {
+                    route: "/keypair*".to_string(),
+                    per_ip: Some(BucketConfig {
+                        burst: 5,
+                        per_minute: 10,
+                    }),
+                    per_key: Some(BucketConfig {
+                        burst: 20,
+                        per_minute: 60,
+                    }),
+                },
+                RouteLimitConfig {
+                    route: "/message/verify*".to_string(),
+                    per_ip: Some(BucketConfig {
+                        burst: 10,
+                        per_minute: 30,
+                    }),
+                    per_key: Some(BucketConfig {
+                        burst: 50,
+                        per_minute: 300,
+                    }),
+                },
+            ],
+            daily_quota_per_ip: None,
+            daily_quota_per_key: None,
+            quota_file: None,
+        }
+    }
+}
+
+/// Token bucket holding up to `burst` requests, refilled at `per_minute`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BucketConfig {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteLimitConfig {
+    /// Route template such as `/keypair` or `/message/verify*`; a trailing * matches a prefix
+    pub route: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_ip: Option<BucketConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_key: Option<BucketConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Text,
+            filter: "info".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One line per event
+    Text,
+    /// Multi-line, human-oriented output for development
+    Pretty,
+    /// One JSON object per event
+    Json,
+}
+
+impl Config {
+    /// Defaults, overlaid by the config file, then environment variables and flags
+    pub fn load(cli: &Cli) -> Result<Self, ConfigError> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+
+        config.apply(cli);
+        if let Some(path) = &config.auth.keys_file {
+            let file: ApiKeyFile = read_toml(path)?;
+            config.auth.file_keys = file.keys;
+        }
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Timeout for a route template such as `/transaction/send`. Unless a rule sets one,
+    /// routes that wait for confirmation outlast `rpc.confirmation_timeout_secs`.
+    pub fn timeout_secs(&self, route: &str) -> u64 {
+        let request_timeout = self.limits.request_timeout_secs;
+        self.limits.rule_timeout_secs(route).unwrap_or_else(|| {
+            if CONFIRMING_ROUTES.contains(&route) {
+                let confirmation = self.rpc.confirmation_timeout_secs;
+                request_timeout.max(confirmation.saturating_add(CONFIRMATION_TIMEOUT_MARGIN_SECS))
+            } else {
+                request_timeout
+            }
+        })
+    }
+
+    /// Optional features that are switched on, as reported by the health routes
+    pub fn enabled_features(&self) -> Vec<&'static str> {
+        [
+            ("docs", self.features.docs),
+            ("local_simulation", self.features.local_simulation),
+            ("custom_cluster_urls", self.features.custom_cluster_urls),
+            ("metrics", self.metrics.enabled),
+            ("auth", self.auth.enabled),
+            ("rate_limit", self.rate_limit.enabled),
+            ("tls", self.tls.enabled()),
+            ("client_certificates", self.tls.client_ca.is_some()),
+            ("unix_socket", self.server.unix_socket.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect()
+    }
+
+    fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        read_toml(path)
+    }
+
+    fn apply(&mut self, cli: &Cli) {
+        let server = &mut self.server;
+        if let Some(bind_address) = cli.bind_address {
+            server.bind_address = bind_address;
+        }
+        if let Some(port) = cli.port {
+            server.port = port;
+        }
+        if let Some(path) = &cli.unix_socket {
+            server.unix_socket = Some(path.clone());
+        }
+        if let Some(mode) = cli.unix_socket_mode {
+            server.unix_socket_mode = mode;
+        }
+        if let Some(timeout) = cli.shutdown_timeout_secs {
+            server.shutdown_timeout_secs = timeout;
+        }
+        if let Some(origins) = &cli.cors_origins {
+            server.cors_origins = origins.iter().map(|origin| origin.trim().to_string()).collect();
+        }
+        if let Some(limit) = cli.body_limit_bytes {
+            server.body_limit_bytes = limit;
+        }
+
+        if let Some(timeout) = cli.request_timeout_secs {
+            self.limits.request_timeout_secs = timeout;
+        }
+        if let Some(max) = cli.max_concurrent_requests {
+            self.limits.max_concurrent_requests = max;
+        }
+
+        let tls = &mut self.tls;
+        if let Some(path) = &cli.tls_cert {
+            tls.cert = Some(path.clone());
+        }
+        if let Some(path) = &cli.tls_key {
+            tls.key = Some(path.clone());
+        }
+        if let Some(path) = &cli.tls_client_ca {
+            tls.client_ca = Some(path.clone());
+        }
+
+        let rpc = &mut self.rpc;
+        if let Some(url) = &cli.rpc_url {
+            rpc.url = Some(url.clone());
+        }
+        if let Some(timeout) = cli.rpc_timeout_secs {
+            rpc.timeout_secs = timeout;
+        }
+        if let Some(timeout) = cli.confirmation_timeout_secs {
+            rpc.confirmation_timeout_secs = timeout;
+        }
+        // An empty URL means "no default endpoint", matching an unset variable
+        rpc.url = rpc.url.take().filter(|url| !url.trim().is_empty());
+
+        let features = &mut self.features;
+        if let Some(docs) = cli.docs {
+            features.docs = docs;
+        }
+        if let Some(local_simulation) = cli.local_simulation {
+            features.local_simulation = local_simulation;
+        }
+        if let Some(custom_cluster_urls) = cli.custom_cluster_urls {
+            features.custom_cluster_urls = custom_cluster_urls;
+        }
+
+        if let Some(enabled) = cli.metrics {
+            self.metrics.enabled = enabled;
+        }
+        if let Some(port) = cli.metrics_port {
+            self.metrics.port = Some(port);
+        }
+
+        if let Some(enabled) = cli.auth {
+            self.auth.enabled = enabled;
+        }
+        if let Some(path) = &cli.api_keys_file {
+            self.auth.keys_file = Some(path.clone());
+        }
+
+        if let Some(enabled) = cli.rate_limit {
+            self.rate_limit.enabled = enabled;
+        }
+        if let Some(path) = &cli.quota_file {
+            self.rate_limit.quota_file = Some(path.clone());
+        }
+
+        if let Some(format) = cli.log_format {
+            self.logging.format = format;
+        }
+        if let Some(filter) = &cli.log_filter {
+            self.logging.filter = filter.clone();
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+
+        if self.server.port == 0 {
+            errors.push("server.port must be between 1 and 65535".to_string());
+        }
+        if self.server.body_limit_bytes == 0 {
+            errors.push("server.body_limit_bytes must be greater than 0".to_string());
+        }
+        if self.server.shutdown_timeout_secs == 0 {
+            errors.push("server.shutdown_timeout_secs must be greater than 0".to_string());
+        }
+        if self.server.unix_socket_mode > 0o777 {
+            errors.push(format!(
+                "server.unix_socket_mode must be at most 0o777, got {:#o}",
+                self.server.unix_socket_mode
+            ));
+        }
+
+        let origins = &self.server.cors_origins;
+        if origins.is_empty() {
+            errors.push("server.cors_origins must not be empty; use [\"*\"] for any".to_string());
+        } else if origins.iter().any(|origin| origin == "*") {
+            if origins.len() > 1 {
+                errors.push("server.cors_origins cannot mix \"*\" with other origins".to_string());
+            }
+        } else {
+            for origin in origins {
+                if !is_http_url(origin) || HeaderValue::from_str(origin).is_err() {
+                    errors.push(format!(
+                        "server.cors_origins: {:?} is not an http(s) origin",
+                        origin
+                    ));
+                }
+            }
+        }
+
+        let tls = &self.tls;
+        if tls.cert.is_some() != tls.key.is_some() {
+            errors.push("tls.cert and tls.key must be set together".to_string());
+        }
+        if tls.client_ca.is_some() && !tls.enabled() {
+            errors.push("tls.client_ca requires tls.cert and tls.key".to_string());
+        }
+        let any_tls = tls.cert.is_some() || tls.key.is_some() || tls.client_ca.is_some();
+        if any_tls && self.server.unix_socket.is_some() {
+            errors.push("server.unix_socket cannot be combined with tls settings".to_string());
+        }
+        for route in tls.client_cert_routes.iter().filter(|route| !route.starts_with('/')) {
+            errors.push(format!("tls.client_cert_routes: {:?} must start with /", route));
+        }
+
+        if self.rpc.timeout_secs == 0 {
+            errors.push("rpc.timeout_secs must be greater than 0".to_string());
+        }
+        if self.rpc.confirmation_timeout_secs == 0 {
+            errors.push("rpc.confirmation_timeout_secs must be greater than 0".to_string());
+        }
+
+        let clusters = &self.rpc.clusters;
+        let urls = [
+            ("rpc.url", self.rpc.url.as_deref()),
+            ("rpc.clusters.mainnet", Some(clusters.mainnet.as_str())),
+            ("rpc.clusters.devnet", Some(clusters.devnet.as_str())),
+            ("rpc.clusters.testnet", Some(clusters.testnet.as_str())),
+            ("rpc.clusters.localnet", Some(clusters.localnet.as_str())),
+        ];
+        for (name, url) in urls {
+            if let Some(url) = url.filter(|url| !is_http_url(url)) {
+                errors.push(format!("{} must be an http(s) URL, got {:?}", name, url));
+            }
+        }
+
+        match self.metrics.port {
+            Some(0) => errors.push("metrics.port must be between 1 and 65535".to_string()),
+            Some(port) if port == self.server.port && self.server.unix_socket.is_none() => {
+                errors.push("metrics.port must differ from server.port".to_string())
+            }
+            _ => {}
+        }
+
+        self.validate_limits(&mut errors);
+        self.validate_auth(&mut errors);
+        self.validate_rate_limit(&mut errors);
+
+        if let Err(err) = EnvFilter::try_new(&self.logging.filter) {
+            errors.push(format!("logging.filter: {}", err));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    fn validate_limits(&self, errors: &mut Vec<String>) {
+        let limits = &self.limits;
+        if limits.request_timeout_secs == 0 {
+            errors.push("limits.request_timeout_secs must be greater than 0".to_string());
+        }
+        if limits.max_concurrent_requests == 0 {
+            errors.push("limits.max_concurrent_requests must be greater than 0".to_string());
+        }
+
+        for rule in &limits.routes {
+            if !rule.route.starts_with('/') {
+                errors.push(format!("limits.routes: route {:?} must start with /", rule.route));
+            }
+            let values = [
+                ("body_limit_bytes", rule.body_limit_bytes.map(|limit| limit as u64)),
+                ("timeout_secs", rule.timeout_secs),
+                ("max_concurrent", rule.max_concurrent.map(|max| max as u64)),
+            ];
+            for (name, _) in values.iter().filter(|(_, value)| *value == Some(0)) {
+                errors.push(format!(
+                    "limits.routes.{}: {} must be greater than 0",
+                    rule.route, name
+                ));
+            }
+        }
+
+        for route in CONFIRMING_ROUTES {
+            let timeout = self.timeout_secs(route);
+            if timeout <= self.rpc.confirmation_timeout_secs {
+                errors.push(format!(
+                    "the {} timeout ({} s) must exceed rpc.confirmation_timeout_secs ({} s)",
+                    route, timeout, self.rpc.confirmation_timeout_secs
+                ));
+            }
+        }
+    }
+
+    fn validate_auth(&self, errors: &mut Vec<String>) {
+        let auth = &self.auth;
+        let keys = auth.keys.iter().chain(&auth.file_keys).collect::<Vec<_>>();
+
+        if auth.enabled && keys.is_empty() {
+            errors.push(
+                "auth.enabled requires at least one key in auth.keys or auth.keys_file".to_string(),
+            );
+        }
+
+        let mut ids = HashSet::new();
+        let mut digests = HashSet::new();
+        for key in keys {
+            if key.id.trim().is_empty() {
+                errors.push("auth.keys: id must not be empty".to_string());
+            } else if !ids.insert(key.id.as_str()) {
+                errors.push(format!("auth.keys: duplicate id {:?}", key.id));
+            }
+
+            if key.sha256.len() != 64 || !key.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+                errors.push(format!("auth.keys.{}: sha256 must be 64 hex characters", key.id));
+            } else if !digests.insert(key.sha256.to_ascii_lowercase()) {
+                errors.push(format!("auth.keys.{}: the same key is configured twice", key.id));
+            }
+
+            if key.scopes.is_empty() {
+                errors.push(format!("auth.keys.{}: scopes must not be empty", key.id));
+            }
+            for route in key.routes.iter().filter(|route| !route.starts_with('/')) {
+                errors.push(format!("auth.keys.{}: route {:?} must start with /", key.id, route));
+            }
+        }
+    }
+
+    fn validate_rate_limit(&self, errors: &mut Vec<String>) {
+        let limits = &self.rate_limit;
+        let mut buckets = vec![
+            ("rate_limit.per_ip".to_string(), limits.per_ip),
+            ("rate_limit.per_key".to_string(), limits.per_key),
+        ];
+        for route in &limits.routes {
+            if !route.route.starts_with('/') {
+                errors.push(format!(
+                    "rate_limit.routes: route {:?} must start with /",
+                    route.route
+                ));
+            }
+            if route.per_ip.is_none() && route.per_key.is_none() {
+                errors.push(format!(
+                    "rate_limit.routes.{}: set per_ip, per_key or both",
+                    route.route
+                ));
+            }
+            let name = format!("rate_limit.routes.{}", route.route);
+            buckets.extend(route.per_ip.map(|bucket| (format!("{}.per_ip", name), bucket)));
+            buckets.extend(route.per_key.map(|bucket| (format!("{}.per_key", name), bucket)));
+        }
+
+        for (name, bucket) in buckets {
+            if bucket.burst == 0 || bucket.per_minute == 0 {
+                errors.push(format!("{}: burst and per_minute must be greater than 0", name));
+            }
+        }
+
+        let quotas = [
+            ("rate_limit.daily_quota_per_ip", limits.daily_quota_per_ip),
+            ("rate_limit.daily_quota_per_key", limits.daily_quota_per_key),
+        ];
+        for (name, _) in quotas.iter().filter(|(_, quota)| *quota == Some(0)) {
+            errors.push(format!("{} must be greater than 0; leave it unset for no quota", name));
+        }
+    }
+}
+
+fn read_toml<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// File permissions written in octal, with or without a `0o` prefix
+fn parse_mode(value: &str) -> Result<u32, String> {
+    let digits = value.strip_prefix("0o").unwrap_or(value);
+    u32::from_str_radix(digits, 8).map_err(|_| format!("{:?} is not an octal mode", value))
+}
+
+fn is_http_url(value: &str) -> bool {
+    reqwest::Url::parse(value)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::OsString,
+        sync::{Mutex, MutexGuard, PoisonError},
+    };
+    use super::*;
+
+    /// Held while the process environment is changed or read by clap
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Environment overrides, restored to their previous values on drop
+    struct EnvGuard {
+        saved: Vec<(&'static str, Option<OsString>)>,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl EnvGuard {
+        fn set(vars: &[(&'static str, &str)]) -> Self {
+            let lock = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+            let saved = vars
+                .iter()
+                .map(|&(name, value)| {
+                    let previous = std::env::var_os(name);
+                    std::env::set_var(name, value);
+                    (name, previous)
+                })
+                .collect();
+            Self { saved, _lock: lock }
+        }
+    }
+
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            for (name, previous) in self.saved.drain(..) {
+                match previous {
+                    Some(value) => std::env::set_var(name, value),
+                    None => std::env::remove_var(name),
+                }
+            }
+        }
+    }
+
+    /// Parse flags as the binary would with `env` set on top of the process environment
+    fn parse_cli(args: &[&str], env: &[(&'static str, &str)]) -> Cli {
+        let _env = EnvGuard::set(env);
+        Cli::try_parse_from(std::iter::once("solana-rust-api").chain(args.iter().copied()))
+            .unwrap()
+    }
+
+    fn write_config(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.toml", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn errors(config: &Config) -> Vec<String> {
+        match config.validate() {
+            Ok(()) => Vec::new(),
+            Err(ConfigError::Invalid(errors)) => errors,
+            Err(other) => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn flags_override_env_which_overrides_the_file() {
+        let path = write_config(
+            "precedence",
+            "[server]\nport = 1000\n\n[limits]\nrequest_timeout_secs = 20\n\n\
+             [rpc]\ntimeout_secs = 10\nconfirmation_timeout_secs = 40\n",
+        );
+        let cli = parse_cli(
+            &["--config", path.to_str().unwrap(), "--port", "3000"],
+            &[("PORT", "2000"), ("RPC_TIMEOUT_SECS", "11")],
+        );
+
+        let config = Config::load(&cli).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(config.server.port, 3000);
+        assert_eq!(config.rpc.timeout_secs, 11);
+        assert_eq!(config.limits.request_timeout_secs, 20);
+        assert_eq!(config.rpc.confirmation_timeout_secs, 40);
+        assert_eq!(config.server.body_limit_bytes, DEFAULT_BODY_LIMIT_BYTES);
+    }
+
+    #[test]
+    fn unix_socket_modes_are_octal() {
+        let cli = parse_cli(&["--unix-socket-mode", "660"], &[]);
+        assert_eq!(cli.unix_socket_mode, Some(0o660));
+        let cli = parse_cli(&["--unix-socket-mode", "0o640"], &[]);
+        assert_eq!(cli.unix_socket_mode, Some(0o640));
+        assert!(parse_mode("0x1a4").is_err());
+        assert!(parse_mode("8").is_err());
+
+        let mut config = Config::default();
+        assert_eq!(config.server.unix_socket_mode, 0o600);
+        config.server.unix_socket_mode = 0o1777;
+        assert_eq!(errors(&config), ["server.unix_socket_mode must be at most 0o777, got 0o1777"]);
+    }
+
+    #[test]
+    fn unknown_file_settings_are_rejected() {
+        let path = write_config("unknown", "[server]\nprot = 1000\n");
+        let cli = parse_cli(&["--config", path.to_str().unwrap()], &[]);
+        let result = Config::load(&cli);
+        fs::remove_file(path).unwrap();
+        assert!(matches!(result, Err(ConfigError::Parse { .. })));
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(errors(&Config::default()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn confirming_routes_follow_the_confirmation_timeout() {
+        let mut config = Config::default();
+        config.rpc.confirmation_timeout_secs = 300;
+        for route in CONFIRMING_ROUTES {
+            assert_eq!(config.timeout_secs(route), 300 + CONFIRMATION_TIMEOUT_MARGIN_SECS);
+        }
+        assert_eq!(config.timeout_secs("/send/sol"), REQUEST_TIMEOUT_SECS);
+        assert!(errors(&config).is_empty());
+
+        config.limits.routes.push(RouteLimitsOverride {
+            route: "/rpc".to_string(),
+            timeout_secs: Some(120),
+            ..RouteLimitsOverride::default()
+        });
+        assert_eq!(
+            errors(&config),
+            ["the /rpc timeout (120 s) must exceed rpc.confirmation_timeout_secs (300 s)"]
+        );
+    }
+
+    #[test]
+    fn validation_reports_every_problem() {
+        let mut config = Config::default();
+        config.server.port = 0;
+        config.server.unix_socket = Some(PathBuf::from("/tmp/api.sock"));
+        config.tls.cert = Some(PathBuf::from("cert.pem"));
+        config.server.cors_origins = vec!["*".to_string(), "https://a.example".to_string()];
+        config.rpc.url = Some("ftp://example.com".to_string());
+        config.limits.routes.push(RouteLimitsOverride {
+            route: "calc/*".to_string(),
+            max_concurrent: Some(0),
+            ..RouteLimitsOverride::default()
+        });
+
+        let errors = errors(&config);
+        for expected in [
+            "server.port must be between 1 and 65535",
+            "server.cors_origins cannot mix \"*\" with other origins",
+            "tls.cert and tls.key must be set together",
+            "server.unix_socket cannot be combined with tls settings",
+            "rpc.url must be an http(s) URL, got \"ftp://example.com\"",
+            "limits.routes: route \"calc/*\" must start with /",
+            "limits.routes.calc/*: max_concurrent must be greater than 0",
+        ] {
+            let found = errors.iter().any(|error| error == expected);
+            assert!(found, "missing {:?} in {:?}", expected, errors);
+        }
+    }
+}
@@ -0,0 +1,701 @@
+use std::fmt::{Debug, Display};
+use num_traits::FromPrimitive;
+use serde_json::{json, Value};
+use solana_sdk::{
+    nonce::state::{State as NonceState, Versions as NonceVersions},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    stake::state::{Meta, StakeStateV2},
+    system_instruction::SystemError,
+    system_program,
+    sysvar::{self, clock::Clock, epoch_schedule::EpochSchedule, rent::Rent, Sysvar},
+};
+use spl_associated_token_account::error::AssociatedTokenAccountError;
+use spl_token_2022::{
+    extension::{
+        cpi_guard::CpiGuard, default_account_state::DefaultAccountState,
+        group_member_pointer::GroupMemberPointer, group_pointer::GroupPointer,
+        interest_bearing_mint::InterestBearingConfig, memo_transfer::MemoTransfer,
+        metadata_pointer::MetadataPointer, mint_close_authority::MintCloseAuthority,
+        permanent_delegate::PermanentDelegate,
+        transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig},
+        transfer_hook::{TransferHook, TransferHookAccount},
+        AccountType, BaseState, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    instruction::MAX_SIGNERS,
+    state::{Account, AccountState, Mint, Multisig},
+};
+use spl_token_group_interface::state::{TokenGroup, TokenGroupMember};
+use spl_token_metadata_interface::state::TokenMetadata;
+use crate::{
+    error::{AppError, ErrorCode},
+    types::ParsedAccountData,
+};
+
+/// Decode raw account data according to the program that owns it
+pub fn decode_account_data(owner: &Pubkey, data: &[u8]) -> Result<ParsedAccountData, AppError> {
+    if *owner == system_program::id() {
+        decode_nonce(data)
+    } else if *owner == spl_token::id() {
+        decode_spl_token(data)
+    } else if *owner == spl_token_2022::id() {
+        decode_spl_token_2022(data)
+    } else if *owner == solana_sdk::stake::program::id() {
+        decode_stake(data)
+    } else if *owner == sysvar::id() {
+        decode_sysvar(data)
+    } else {
+        Err(AppError::invalid(
+            ErrorCode::InvalidValue,
+            "owner",
+            format!("Unsupported account owner: {}", owner),
+        ))
+    }
+}
+
+/// Token balance held by an SPL Token or Token-2022 account, if the data is one
+pub fn token_account_amount(owner: &Pubkey, data: &[u8]) -> Option<u64> {
+    if *owner != spl_token::id() && *owner != spl_token_2022::id() {
+        return None;
+    }
+    if data.len() != Account::LEN && data.get(Account::LEN) != Some(&(AccountType::Account as u8)) {
+        return None;
+    }
+    StateWithExtensions::<Account>::unpack(data)
+        .ok()
+        .map(|account| account.base.amount)
+}
+
+/// Decimals of an SPL Token or Token-2022 mint, if the data is one
+pub fn mint_decimals(owner: &Pubkey, data: &[u8]) -> Option<u8> {
+    if *owner != spl_token::id() && *owner != spl_token_2022::id() {
+        return None;
+    }
+    if data.len() != Mint::LEN && data.get(Account::LEN) != Some(&(AccountType::Mint as u8)) {
+        return None;
+    }
+    StateWithExtensions::<Mint>::unpack(data)
+        .ok()
+        .map(|mint| mint.base.decimals)
+}
+
+/// Name and message for a custom instruction error code of a known program
+pub fn decode_custom_error(program_id: &Pubkey, code: u32) -> Option<(String, String)> {
+    fn describe<E: Debug + Display>(error: E) -> (String, String) {
+        (format!("{:?}", error), error.to_string())
+    }
+
+    if *program_id == spl_token::id() {
+        spl_token::error::TokenError::from_u32(code).map(describe)
+    } else if *program_id == spl_token_2022::id() {
+        spl_token_2022::error::TokenError::from_u32(code).map(describe)
+    } else if *program_id == system_program::id() {
+        SystemError::from_u32(code).map(describe)
+    } else if *program_id == spl_associated_token_account::id() {
+        AssociatedTokenAccountError::from_u32(code).map(describe)
+    } else {
+        None
+    }
+}
+
+fn parsed(program: &str, account_type: &str, info: Value) -> ParsedAccountData {
+    ParsedAccountData {
+        program: program.to_string(),
+        account_type: account_type.to_string(),
+        info,
+    }
+}
+
+fn decode_error(kind: &str) -> AppError {
+    AppError::invalid(
+        ErrorCode::InvalidValue,
+        "data",
+        format!("Failed to decode {} account data", kind),
+    )
+}
+
+fn invalid_length(message: String) -> AppError {
+    AppError::invalid(ErrorCode::InvalidLength, "data", message)
+}
+
+fn decode_nonce(data: &[u8]) -> Result<ParsedAccountData, AppError> {
+    if data.len() != NonceState::size() {
+        return Err(invalid_length(format!(
+            "Invalid nonce account length: expected {} bytes, got {}",
+            NonceState::size(),
+            data.len()
+        )));
+    }
+
+    let versions: NonceVersions = bincode::deserialize(data).map_err(|_| decode_error("nonce"))?;
+    match versions.state() {
+        NonceState::Uninitialized => Ok(parsed("nonce", "uninitialized", json!({}))),
+        NonceState::Initialized(nonce) => Ok(parsed(
+            "nonce",
+            "initialized",
+            json!({
+                "authority": nonce.authority.to_string(),
+                "blockhash": nonce.blockhash().to_string(),
+                "lamports_per_signature": nonce.fee_calculator.lamports_per_signature,
+            }),
+        )),
+    }
+}
+
+fn decode_spl_token(data: &[u8]) -> Result<ParsedAccountData, AppError> {
+    // SPL Token states share their byte layout with the Token-2022 base states,
+    // so both programs are rendered through the same info helpers.
+    match data.len() {
+        Mint::LEN => {
+            let mint = Mint::unpack(data).map_err(|_| decode_error("mint"))?;
+            Ok(parsed("spl-token", "mint", mint_info(&mint)))
+        }
+        Account::LEN => {
+            let account = Account::unpack(data).map_err(|_| decode_error("token"))?;
+            Ok(parsed("spl-token", "account", token_account_info(&account)))
+        }
+        Multisig::LEN => decode_multisig("spl-token", data),
+        len => Err(invalid_length(format!(
+            "Invalid SPL Token account length: {} bytes",
+            len
+        ))),
+    }
+}
+
+fn decode_spl_token_2022(data: &[u8]) -> Result<ParsedAccountData, AppError> {
+    // Extended accounts carry their type in the byte right after the base account
+    let account_type = data.get(Account::LEN).copied();
+
+    if data.len() == Multisig::LEN {
+        decode_multisig("spl-token-2022", data)
+    } else if data.len() == Mint::LEN || account_type == Some(AccountType::Mint as u8) {
+        let mint =
+            StateWithExtensions::<Mint>::unpack(data).map_err(|_| decode_error("mint"))?;
+        let mut info = mint_info(&mint.base);
+        info["extensions"] = extensions_info(&mint);
+        Ok(parsed("spl-token-2022", "mint", info))
+    } else if data.len() == Account::LEN || account_type == Some(AccountType::Account as u8) {
+        let account =
+            StateWithExtensions::<Account>::unpack(data).map_err(|_| decode_error("token"))?;
+        let mut info = token_account_info(&account.base);
+        info["extensions"] = extensions_info(&account);
+        Ok(parsed("spl-token-2022", "account", info))
+    } else {
+        Err(invalid_length(format!(
+            "Invalid Token-2022 account length: {} bytes",
+            data.len()
+        )))
+    }
+}
+
+fn decode_multisig(program: &str, data: &[u8]) -> Result<ParsedAccountData, AppError> {
+    let multisig = Multisig::unpack(data).map_err(|_| decode_error("multisig"))?;
+    // `unpack` only checks the initialized flag, so the counts are still untrusted here
+    if multisig.n as usize > MAX_SIGNERS || multisig.m > multisig.n {
+        return Err(AppError::invalid(
+            ErrorCode::InvalidValue,
+            "data",
+            format!(
+                "Invalid multisig: {} of {} signers required, at most {} allowed",
+                multisig.m, multisig.n, MAX_SIGNERS
+            ),
+        ));
+    }
+    Ok(parsed(program, "multisig", multisig_info(&multisig)))
+}
+
+fn extensions_info<T: BaseState>(state: &impl BaseStateWithExtensions<T>) -> Value {
+    let extensions = state
+        .get_extension_types()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|extension| {
+            json!({
+                "extension": format!("{:?}", extension),
+                "state": extension_state(state, extension),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!(extensions)
+}
+
+/// Decoded fields of a single extension; `null` for markers and confidential state
+fn extension_state<T: BaseState>(
+    state: &impl BaseStateWithExtensions<T>,
+    extension: ExtensionType,
+) -> Value {
+    let decoded = match extension {
+        ExtensionType::TransferFeeConfig => {
+            state.get_extension::<TransferFeeConfig>().map(|config| {
+                let config_authority = optional_pubkey(config.transfer_fee_config_authority);
+                let withdraw_authority = optional_pubkey(config.withdraw_withheld_authority);
+                json!({
+                    "transfer_fee_config_authority": config_authority,
+                    "withdraw_withheld_authority": withdraw_authority,
+                    "withheld_amount": u64::from(config.withheld_amount).to_string(),
+                    "older_transfer_fee": transfer_fee_info(&config.older_transfer_fee),
+                    "newer_transfer_fee": transfer_fee_info(&config.newer_transfer_fee),
+                })
+            })
+        }
+        ExtensionType::TransferFeeAmount => {
+            state.get_extension::<TransferFeeAmount>().map(|amount| {
+                json!({ "withheld_amount": u64::from(amount.withheld_amount).to_string() })
+            })
+        }
+        ExtensionType::MintCloseAuthority => {
+            state.get_extension::<MintCloseAuthority>().map(|close| {
+                json!({ "close_authority": optional_pubkey(close.close_authority) })
+            })
+        }
+        ExtensionType::DefaultAccountState => {
+            state.get_extension::<DefaultAccountState>().map(|default| {
+                let account_state = AccountState::try_from(default.state)
+                    .map(|account_state| format!("{:?}", account_state).to_lowercase())
+                    .unwrap_or_else(|_| default.state.to_string());
+                json!({ "state": account_state })
+            })
+        }
+        ExtensionType::MemoTransfer => state.get_extension::<MemoTransfer>().map(|memo| {
+            json!({
+                "require_incoming_transfer_memos": bool::from(memo.require_incoming_transfer_memos),
+            })
+        }),
+        ExtensionType::InterestBearingConfig => {
+            state.get_extension::<InterestBearingConfig>().map(|config| {
+                json!({
+                    "rate_authority": optional_pubkey(config.rate_authority),
+                    "initialization_timestamp": i64::from(config.initialization_timestamp),
+                    "pre_update_average_rate": i16::from(config.pre_update_average_rate),
+                    "last_update_timestamp": i64::from(config.last_update_timestamp),
+                    "current_rate": i16::from(config.current_rate),
+                })
+            })
+        }
+        ExtensionType::CpiGuard => state
+            .get_extension::<CpiGuard>()
+            .map(|guard| json!({ "lock_cpi": bool::from(guard.lock_cpi) })),
+        ExtensionType::PermanentDelegate => state
+            .get_extension::<PermanentDelegate>()
+            .map(|delegate| json!({ "delegate": optional_pubkey(delegate.delegate) })),
+        ExtensionType::TransferHook => state.get_extension::<TransferHook>().map(|hook| {
+            json!({
+                "authority": optional_pubkey(hook.authority),
+                "program_id": optional_pubkey(hook.program_id),
+            })
+        }),
+        ExtensionType::TransferHookAccount => state
+            .get_extension::<TransferHookAccount>()
+            .map(|hook| json!({ "transferring": bool::from(hook.transferring) })),
+        ExtensionType::MetadataPointer => state.get_extension::<MetadataPointer>().map(|pointer| {
+            json!({
+                "authority": optional_pubkey(pointer.authority),
+                "metadata_address": optional_pubkey(pointer.metadata_address),
+            })
+        }),
+        ExtensionType::TokenMetadata => state
+            .get_variable_len_extension::<TokenMetadata>()
+            .map(|metadata| {
+                json!({
+                    "update_authority": optional_pubkey(metadata.update_authority),
+                    "mint": metadata.mint.to_string(),
+                    "name": metadata.name,
+                    "symbol": metadata.symbol,
+                    "uri": metadata.uri,
+                    "additional_metadata": metadata.additional_metadata,
+                })
+            }),
+        ExtensionType::GroupPointer => state.get_extension::<GroupPointer>().map(|pointer| {
+            json!({
+                "authority": optional_pubkey(pointer.authority),
+                "group_address": optional_pubkey(pointer.group_address),
+            })
+        }),
+        ExtensionType::TokenGroup => state.get_extension::<TokenGroup>().map(|group| {
+            json!({
+                "update_authority": optional_pubkey(group.update_authority),
+                "mint": group.mint.to_string(),
+                "size": u32::from(group.size),
+                "max_size": u32::from(group.max_size),
+            })
+        }),
+        ExtensionType::GroupMemberPointer => {
+            state.get_extension::<GroupMemberPointer>().map(|pointer| {
+                json!({
+                    "authority": optional_pubkey(pointer.authority),
+                    "member_address": optional_pubkey(pointer.member_address),
+                })
+            })
+        }
+        ExtensionType::TokenGroupMember => state.get_extension::<TokenGroupMember>().map(|member| {
+            json!({
+                "mint": member.mint.to_string(),
+                "group": member.group.to_string(),
+                "member_number": u32::from(member.member_number),
+            })
+        }),
+        _ => return Value::Null,
+    };
+    decoded.unwrap_or(Value::Null)
+}
+
+fn transfer_fee_info(fee: &TransferFee) -> Value {
+    json!({
+        "epoch": u64::from(fee.epoch),
+        "maximum_fee": u64::from(fee.maximum_fee).to_string(),
+        "transfer_fee_basis_points": u16::from(fee.transfer_fee_basis_points),
+    })
+}
+
+fn decode_stake(data: &[u8]) -> Result<ParsedAccountData, AppError> {
+    let state: StakeStateV2 = bincode::deserialize(data).map_err(|_| decode_error("stake"))?;
+    match state {
+        StakeStateV2::Uninitialized => Ok(parsed("stake", "uninitialized", json!({}))),
+        StakeStateV2::RewardsPool => Ok(parsed("stake", "rewards_pool", json!({}))),
+        StakeStateV2::Initialized(meta) => {
+            Ok(parsed("stake", "initialized", json!({ "meta": stake_meta_info(&meta) })))
+        }
+        StakeStateV2::Stake(meta, stake, _) => Ok(parsed(
+            "stake",
+            "delegated",
+            json!({
+                "meta": stake_meta_info(&meta),
+                "stake": {
+                    "delegation": {
+                        "voter": stake.delegation.voter_pubkey.to_string(),
+                        "stake": stake.delegation.stake,
+                        "activation_epoch": stake.delegation.activation_epoch,
+                        "deactivation_epoch": stake.delegation.deactivation_epoch,
+                    },
+                    "credits_observed": stake.credits_observed,
+                },
+            }),
+        )),
+    }
+}
+
+fn decode_sysvar(data: &[u8]) -> Result<ParsedAccountData, AppError> {
+    // Sysvar accounts share one owner; the fixed-size ones are told apart by length
+    if data.len() == Clock::size_of() {
+        let clock: Clock = bincode::deserialize(data).map_err(|_| decode_error("clock"))?;
+        Ok(parsed(
+            "sysvar",
+            "clock",
+            json!({
+                "slot": clock.slot,
+                "epoch_start_timestamp": clock.epoch_start_timestamp,
+                "epoch": clock.epoch,
+                "leader_schedule_epoch": clock.leader_schedule_epoch,
+                "unix_timestamp": clock.unix_timestamp,
+            }),
+        ))
+    } else if data.len() == Rent::size_of() {
+        let rent: Rent = bincode::deserialize(data).map_err(|_| decode_error("rent"))?;
+        Ok(parsed(
+            "sysvar",
+            "rent",
+            json!({
+                "lamports_per_byte_year": rent.lamports_per_byte_year,
+                "exemption_threshold": rent.exemption_threshold,
+                "burn_percent": rent.burn_percent,
+            }),
+        ))
+    } else if data.len() == EpochSchedule::size_of() {
+        let schedule: EpochSchedule =
+            bincode::deserialize(data).map_err(|_| decode_error("epoch schedule"))?;
+        Ok(parsed(
+            "sysvar",
+            "epoch_schedule",
+            json!({
+                "slots_per_epoch": schedule.slots_per_epoch,
+                "leader_schedule_slot_offset": schedule.leader_schedule_slot_offset,
+                "warmup": schedule.warmup,
+                "first_normal_epoch": schedule.first_normal_epoch,
+                "first_normal_slot": schedule.first_normal_slot,
+            }),
+        ))
+    } else {
+        Err(invalid_length(format!(
+            "Unsupported sysvar account length: {} bytes",
+            data.len()
+        )))
+    }
+}
+
+fn stake_meta_info(meta: &Meta) -> Value {
+    json!({
+        "rent_exempt_reserve": meta.rent_exempt_reserve,
+        "authorized": {
+            "staker": meta.authorized.staker.to_string(),
+            "withdrawer": meta.authorized.withdrawer.to_string(),
+        },
+        "lockup": {
+            "unix_timestamp": meta.lockup.unix_timestamp,
+            "epoch": meta.lockup.epoch,
+            "custodian": meta.lockup.custodian.to_string(),
+        },
+    })
+}
+
+fn mint_info(mint: &Mint) -> Value {
+    json!({
+        "mint_authority": optional_pubkey(mint.mint_authority),
+        "supply": mint.supply.to_string(),
+        "decimals": mint.decimals,
+        "is_initialized": mint.is_initialized,
+        "freeze_authority": optional_pubkey(mint.freeze_authority),
+    })
+}
+
+fn token_account_info(account: &Account) -> Value {
+    json!({
+        "mint": account.mint.to_string(),
+        "owner": account.owner.to_string(),
+        "amount": account.amount.to_string(),
+        "delegate": optional_pubkey(account.delegate),
+        "state": format!("{:?}", account.state).to_lowercase(),
+        "is_native": Option::<u64>::from(account.is_native),
+        "delegated_amount": account.delegated_amount.to_string(),
+        "close_authority": optional_pubkey(account.close_authority),
+    })
+}
+
+fn multisig_info(multisig: &Multisig) -> Value {
+    let signers = multisig.signers[..multisig.n as usize]
+        .iter()
+        .map(|signer| signer.to_string())
+        .collect::<Vec<_>>();
+    json!({
+        "num_required_signers": multisig.m,
+        "num_valid_signers": multisig.n,
+        "is_initialized": multisig.is_initialized,
+        "signers": signers,
+    })
+}
+
+fn optional_pubkey(value: impl Into<Option<Pubkey>>) -> Option<String> {
+    value.into().map(|pubkey| pubkey.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{
+        hash::Hash,
+        nonce::state::{Data as NonceData, DurableNonce},
+        program_option::COption,
+        stake::{
+            stake_flags::StakeFlags,
+            state::{Authorized, Delegation, Lockup, Stake},
+        },
+    };
+    use spl_token_2022::extension::{
+        immutable_owner::ImmutableOwner, BaseStateWithExtensionsMut, StateWithExtensionsMut,
+    };
+    use super::*;
+
+    fn decode(owner: &Pubkey, data: &[u8]) -> (String, String, Value) {
+        let Ok(parsed) = decode_account_data(owner, data) else {
+            panic!("failed to decode {} bytes owned by {}", data.len(), owner);
+        };
+        (parsed.program, parsed.account_type, parsed.info)
+    }
+
+    fn decode_error_code(owner: &Pubkey, data: &[u8]) -> ErrorCode {
+        decode_account_data(owner, data).err().unwrap().code()
+    }
+
+    fn mint(authority: Pubkey) -> Mint {
+        Mint {
+            mint_authority: COption::Some(authority),
+            supply: 1_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+    }
+
+    fn token_account(mint: Pubkey, owner: Pubkey) -> Account {
+        Account {
+            mint,
+            owner,
+            amount: 42,
+            state: AccountState::Initialized,
+            ..Account::default()
+        }
+    }
+
+    fn multisig_data(m: u8, n: u8) -> Vec<u8> {
+        let mut data = vec![0; Multisig::LEN];
+        let multisig = Multisig {
+            m: m.min(MAX_SIGNERS as u8),
+            n: n.min(MAX_SIGNERS as u8),
+            is_initialized: true,
+            signers: [Pubkey::new_unique(); MAX_SIGNERS],
+        };
+        Multisig::pack(multisig, &mut data).unwrap();
+        // Counts beyond what `pack` accepts are written directly
+        data[0] = m;
+        data[1] = n;
+        data
+    }
+
+    #[test]
+    fn spl_token_mints_and_accounts() {
+        let authority = Pubkey::new_unique();
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(mint(authority), &mut data).unwrap();
+        let (program, kind, info) = decode(&spl_token::id(), &data);
+        assert_eq!((program.as_str(), kind.as_str()), ("spl-token", "mint"));
+        assert_eq!(info["mint_authority"], authority.to_string());
+        assert_eq!(info["supply"], "1000000");
+        assert_eq!(info["decimals"], 6);
+        assert!(info["freeze_authority"].is_null());
+
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0; Account::LEN];
+        Account::pack(token_account(mint, owner), &mut data).unwrap();
+        let (_, kind, info) = decode(&spl_token::id(), &data);
+        assert_eq!(kind, "account");
+        assert_eq!(info["mint"], mint.to_string());
+        assert_eq!(info["owner"], owner.to_string());
+        assert_eq!(info["amount"], "42");
+        assert_eq!(info["state"], "initialized");
+        assert_eq!(token_account_amount(&spl_token::id(), &data), Some(42));
+
+        assert_eq!(decode_error_code(&spl_token::id(), &[0; 10]), ErrorCode::InvalidLength);
+    }
+
+    #[test]
+    fn multisig_lists_only_the_valid_signers() {
+        for owner in [spl_token::id(), spl_token_2022::id()] {
+            let (_, kind, info) = decode(&owner, &multisig_data(2, 3));
+            assert_eq!(kind, "multisig");
+            assert_eq!(info["num_required_signers"], 2);
+            assert_eq!(info["signers"].as_array().unwrap().len(), 3);
+        }
+    }
+
+    #[test]
+    fn malformed_multisig_counts_are_rejected() {
+        for (m, n) in [(1, MAX_SIGNERS as u8 + 1), (1, u8::MAX), (3, 2)] {
+            for owner in [spl_token::id(), spl_token_2022::id()] {
+                let code = decode_error_code(&owner, &multisig_data(m, n));
+                assert_eq!(code, ErrorCode::InvalidValue, "m = {}, n = {}", m, n);
+            }
+        }
+    }
+
+    #[test]
+    fn token_2022_extensions_are_decoded() {
+        let close_authority = Pubkey::new_unique();
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[
+            ExtensionType::MintCloseAuthority,
+        ])
+        .unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        state
+            .init_extension::<MintCloseAuthority>(true)
+            .unwrap()
+            .close_authority = Some(close_authority).try_into().unwrap();
+        state.base = mint(Pubkey::new_unique());
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        let (program, kind, info) = decode(&spl_token_2022::id(), &data);
+        assert_eq!((program.as_str(), kind.as_str()), ("spl-token-2022", "mint"));
+        assert_eq!(info["decimals"], 6);
+        assert_eq!(info["extensions"][0]["extension"], "MintCloseAuthority");
+        assert_eq!(
+            info["extensions"][0]["state"]["close_authority"],
+            close_authority.to_string()
+        );
+        assert_eq!(mint_decimals(&spl_token_2022::id(), &data), Some(6));
+
+        let len = ExtensionType::try_calculate_account_len::<Account>(&[
+            ExtensionType::ImmutableOwner,
+        ])
+        .unwrap();
+        let mut data = vec![0; len];
+        let mut state =
+            StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut data).unwrap();
+        state.init_extension::<ImmutableOwner>(true).unwrap();
+        state.base = token_account(Pubkey::new_unique(), Pubkey::new_unique());
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        let (_, kind, info) = decode(&spl_token_2022::id(), &data);
+        assert_eq!(kind, "account");
+        assert_eq!(info["amount"], "42");
+        assert_eq!(info["extensions"][0]["extension"], "ImmutableOwner");
+        assert!(info["extensions"][0]["state"].is_null());
+        assert_eq!(token_account_amount(&spl_token_2022::id(), &data), Some(42));
+    }
+
+    #[test]
+    fn nonce_accounts() {
+        let authority = Pubkey::new_unique();
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let nonce = NonceData::new(authority, durable_nonce, 5_000);
+        let data = bincode::serialize(&NonceVersions::new(NonceState::Initialized(nonce)));
+        let (program, kind, info) = decode(&system_program::id(), &data.unwrap());
+        assert_eq!((program.as_str(), kind.as_str()), ("nonce", "initialized"));
+        assert_eq!(info["authority"], authority.to_string());
+        assert_eq!(info["blockhash"], durable_nonce.as_hash().to_string());
+        assert_eq!(info["lamports_per_signature"], 5_000);
+
+        let code = decode_error_code(&system_program::id(), &[0; 10]);
+        assert_eq!(code, ErrorCode::InvalidLength);
+    }
+
+    #[test]
+    fn stake_accounts() {
+        let (staker, voter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let meta = Meta {
+            rent_exempt_reserve: 2_282_880,
+            authorized: Authorized {
+                staker,
+                withdrawer: staker,
+            },
+            lockup: Lockup::default(),
+        };
+        let stake = Stake {
+            delegation: Delegation::new(&voter, 1_000_000_000, 10),
+            credits_observed: 7,
+        };
+        let mut data = bincode::serialize(&StakeStateV2::Stake(meta, stake, StakeFlags::empty()));
+        let data = data.as_mut().unwrap();
+        data.resize(StakeStateV2::size_of(), 0);
+
+        let (program, kind, info) = decode(&solana_sdk::stake::program::id(), data);
+        assert_eq!((program.as_str(), kind.as_str()), ("stake", "delegated"));
+        assert_eq!(info["meta"]["authorized"]["staker"], staker.to_string());
+        assert_eq!(info["stake"]["delegation"]["voter"], voter.to_string());
+        assert_eq!(info["stake"]["delegation"]["activation_epoch"], 10);
+        assert_eq!(info["stake"]["credits_observed"], 7);
+    }
+
+    #[test]
+    fn sysvars_are_told_apart_by_length() {
+        let clock = Clock {
+            slot: 166_598,
+            epoch: 27,
+            unix_timestamp: 1_700_000_000,
+            ..Clock::default()
+        };
+        let (program, kind, info) = decode(&sysvar::id(), &bincode::serialize(&clock).unwrap());
+        assert_eq!((program.as_str(), kind.as_str()), ("sysvar", "clock"));
+        assert_eq!(info["slot"], 166_598);
+        assert_eq!(info["unix_timestamp"], 1_700_000_000);
+
+        let rent = bincode::serialize(&Rent::default()).unwrap();
+        let (_, kind, info) = decode(&sysvar::id(), &rent);
+        assert_eq!(kind, "rent");
+        assert_eq!(info["lamports_per_byte_year"], Rent::default().lamports_per_byte_year);
+
+        let schedule = bincode::serialize(&EpochSchedule::default()).unwrap();
+        assert_eq!(decode(&sysvar::id(), &schedule).1, "epoch_schedule");
+
+        assert_eq!(decode_error_code(&sysvar::id(), &[0; 3]), ErrorCode::InvalidLength);
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+};
+use axum::{
+    async_trait,
+    body::{to_bytes, Body},
+    extract::{ConnectInfo, FromRequestParts},
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE, EXPECT, TRANSFER_ENCODING},
+        request::Parts,
+        HeaderMap, HeaderValue, Method, Request, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Router,
+};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde_json::{Map, Value};
+use tower::Service;
+use crate::{
+    config::{Config, DISPATCH_CONCURRENCY},
+    error::{AppError, ErrorCode},
+    tls::ClientCertificate,
+};
+
+/// A route callable through `/rpc` and `/batch`
+pub struct Operation {
+    /// Name of the handler serving the route
+    pub name: &'static str,
+    pub method: Method,
+    pub route: &'static str,
+}
+
+/// Every API operation; health, metrics and docs routes are not operations
+pub static OPERATIONS: &[Operation] = &[
+    post("generate_keypair", "/keypair"),
+    post("generate_secp256k1_keypair", "/keypair/secp256k1"),
+    post("create_token", "/token/create"),
+    post("mint_token", "/token/mint"),
+    post("sign_message", "/message/sign"),
+    post("verify_message", "/message/verify"),
+    post("verify_message_batch", "/message/verify/batch"),
+    post("send_sol", "/send/sol"),
+    post("send_token", "/send/token"),
+    post("build_ed25519_instruction", "/instruction/ed25519"),
+    post("build_secp256k1_instruction", "/instruction/secp256k1"),
+    post("derive_eth_address", "/ethereum/address"),
+    get("get_cluster_info", "/cluster/info"),
+    get("get_account", "/account/:pubkey"),
+    get("get_token_holdings", "/account/:pubkey/tokens"),
+    post("build_transaction", "/transaction/build"),
+    post("send_transaction", "/transaction/send"),
+    post("simulate_transaction", "/transaction/simulate"),
+    post("simulate_local", "/simulate/local"),
+    post("decode_account", "/decode/account"),
+    post("calc_rent", "/calc/rent"),
+    post("calc_fee", "/calc/fee"),
+];
+
+const fn post(name: &'static str, route: &'static str) -> Operation {
+    Operation {
+        name,
+        method: Method::POST,
+        route,
+    }
+}
+
+const fn get(name: &'static str, route: &'static str) -> Operation {
+    Operation {
+        name,
+        method: Method::GET,
+        route,
+    }
+}
+
+impl Operation {
+    /// Look up an operation by handler name, e.g. `send_sol`
+    pub fn find(name: &str) -> Option<&'static Operation> {
+        OPERATIONS.iter().find(|operation| operation.name == name)
+    }
+
+    /// Look up an operation by route template, e.g. `/send/sol`
+    pub fn at(route: &str) -> Option<&'static Operation> {
+        OPERATIONS.iter().find(|operation| operation.route == route)
+    }
+
+    /// Whether the route is served; mirrors the feature checks in `routes::api_routes`
+    pub fn enabled(&self, config: &Config) -> bool {
+        self.route != "/simulate/local" || config.features.local_simulation
+    }
+}
+
+/// Calls of a batch over `routes` to run at once: `DISPATCH_CONCURRENCY`, or fewer when
+/// one of the routes allows fewer concurrent requests
+pub fn concurrency<'a>(config: &Config, routes: impl IntoIterator<Item = &'a str>) -> usize {
+    routes
+        .into_iter()
+        .filter_map(|route| config.limits.max_concurrent(route))
+        .fold(DISPATCH_CONCURRENCY, usize::min)
+}
+
+/// Marks requests made by the dispatcher, which run within the request that asked for them
+#[derive(Clone, Copy)]
+pub struct Dispatched;
+
+/// Headers and connection details of the request that asked for a dispatch, passed on to
+/// each call so that it is authenticated and rate limited as the same client
+pub struct Caller {
+    headers: HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    cert: Option<ClientCertificate>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let mut headers = parts.headers.clone();
+        for name in [CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING, EXPECT] {
+            headers.remove(name);
+        }
+
+        Ok(Self {
+            headers,
+            peer: parts.extensions.get::<ConnectInfo<SocketAddr>>().cloned(),
+            cert: parts.extensions.get::<ClientCertificate>().cloned(),
+        })
+    }
+}
+
+/// Status and response envelope of a dispatched call
+pub struct Outcome {
+    pub status: StatusCode,
+    /// Set when the call failed
+    pub code: Option<ErrorCode>,
+    pub body: Value,
+}
+
+impl Outcome {
+    /// Envelope for a call that failed before reaching its route
+    pub async fn error(error: AppError) -> Self {
+        Self::read(error.into_response()).await
+    }
+
+    async fn read(response: Response) -> Self {
+        let status = response.status();
+        let code = response.extensions().get::<ErrorCode>().copied();
+        let body = match to_bytes(response.into_body(), usize::MAX).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+            Err(_) => Value::Null,
+        };
+        Self { status, code, body }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+}
+
+/// Calls operations in-process through the application router, so every layer -
+/// authentication, rate limits, request limits, metrics - applies to each call as if
+/// it had been made over HTTP
+#[derive(Clone, Default)]
+pub struct Dispatcher {
+    app: Arc<OnceLock<Router>>,
+}
+
+impl Dispatcher {
+    /// Hand over the finished application; it cannot exist before the state holding
+    /// this dispatcher does
+    pub fn install(&self, app: Router) {
+        if self.app.set(app).is_err() {
+            tracing::warn!("dispatcher already installed");
+        }
+    }
+
+    /// Call `operation` with `params` as its JSON body or, for reads, its path and
+    /// query parameters
+    pub async fn call(&self, operation: &Operation, params: Value, caller: &Caller) -> Outcome {
+        let Some(app) = self.app.get() else {
+            tracing::error!("dispatcher used before the application was installed");
+            return Outcome::error(AppError::Internal).await;
+        };
+        let req = match request(operation, params, caller) {
+            Ok(req) => req,
+            Err(err) => return Outcome::error(err).await,
+        };
+
+        // A router is always ready, so it can be called without polling first
+        match app.clone().call(req).await {
+            Ok(response) => Outcome::read(response).await,
+            Err(never) => match never {},
+        }
+    }
+}
+
+fn request(
+    operation: &Operation,
+    params: Value,
+    caller: &Caller,
+) -> Result<Request<Body>, AppError> {
+    let (uri, body) = if operation.method == Method::GET {
+        (read_uri(operation.route, params)?, Body::empty())
+    } else {
+        let params = match params {
+            Value::Null => Value::Object(Map::new()),
+            params => params,
+        };
+        (operation.route.to_string(), Body::from(params.to_string()))
+    };
+
+    let mut req = Request::builder()
+        .method(operation.method.clone())
+        .uri(uri)
+        .body(body)
+        .map_err(|err| {
+            tracing::error!(error = %err, route = operation.route, "failed to build request");
+            AppError::Internal
+        })?;
+
+    *req.headers_mut() = caller.headers.clone();
+    if operation.method == Method::POST {
+        req.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    }
+    if let Some(peer) = caller.peer {
+        req.extensions_mut().insert(peer);
+    }
+    if let Some(cert) = &caller.cert {
+        req.extensions_mut().insert(cert.clone());
+    }
+    req.extensions_mut().insert(Dispatched);
+
+    Ok(req)
+}
+
+/// Fill `:param` segments from the params object and pass the remaining fields as query
+/// parameters
+fn read_uri(route: &str, params: Value) -> Result<String, AppError> {
+    let mut params = match params {
+        Value::Null => Map::new(),
+        Value::Object(params) => params,
+        _ => {
+            return Err(AppError::request(
+                ErrorCode::InvalidBody,
+                "Invalid request body: params must be an object",
+            ))
+        }
+    };
+
+    let mut path = Vec::new();
+    for segment in route.split('/') {
+        let Some(name) = segment.strip_prefix(':') else {
+            path.push(segment.to_string());
+            continue;
+        };
+        let value = params.remove(name).ok_or_else(|| AppError::missing(name))?;
+        path.push(utf8_percent_encode(&scalar(name, value)?, NON_ALPHANUMERIC).to_string());
+    }
+
+    let mut query = Vec::new();
+    for (name, value) in params {
+        if value.is_null() {
+            continue;
+        }
+        let value = scalar(&name, value)?;
+        query.push(format!(
+            "{}={}",
+            utf8_percent_encode(&name, NON_ALPHANUMERIC),
+            utf8_percent_encode(&value, NON_ALPHANUMERIC)
+        ));
+    }
+
+    let path = path.join("/");
+    Ok(if query.is_empty() {
+        path
+    } else {
+        format!("{}?{}", path, query.join("&"))
+    })
+}
+
+fn scalar(name: &str, value: Value) -> Result<String, AppError> {
+    match value {
+        Value::String(value) => Ok(value),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        _ => Err(AppError::invalid(
+            ErrorCode::InvalidBody,
+            name,
+            format!("Invalid request body at {}: expected a string, number or boolean", name),
+        )),
+    }
+}
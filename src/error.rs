@@ -1,42 +1,266 @@
 use axum::{
     response::{IntoResponse, Response},
-    http::StatusCode,
-    extract::rejection::JsonRejection,
+    http::{
+        header::{RETRY_AFTER, WWW_AUTHENTICATE},
+        HeaderValue, StatusCode,
+    },
 };
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use crate::types::ApiResponse;
+use utoipa::ToSchema;
+use crate::{telemetry, types::ApiResponse};
+
+/// Machine-readable error codes carried in the error envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    InvalidJson,
+    InvalidBody,
+    UnsupportedMediaType,
+    PayloadTooLarge,
+    ValidationFailed,
+    MissingField,
+    ConflictingFields,
+    InvalidPubkey,
+    InvalidBase58,
+    InvalidBase64,
+    InvalidHex,
+    InvalidLength,
+    InvalidSecretKey,
+    InvalidPublicKey,
+    InvalidSignature,
+    InvalidTransaction,
+    InvalidMessage,
+    /// A value outside the accepted set or range, e.g. an unknown account shortcut
+    InvalidValue,
+    InvalidAmount,
+    AmountOutOfRange,
+    AmountPrecision,
+    SameAccount,
+    EmptyBatch,
+    TooManyItems,
+    InvalidCluster,
+    RpcNotConfigured,
+    Unauthorized,
+    Forbidden,
+    RateLimited,
+    QuotaExceeded,
+    NotFound,
+    MethodNotAllowed,
+    RpcError,
+    Timeout,
+    Overloaded,
+    NotReady,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidBody => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::RateLimited | ErrorCode::QuotaExceeded => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            ErrorCode::RpcError => StatusCode::BAD_GATEWAY,
+            ErrorCode::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            ErrorCode::Overloaded | ErrorCode::NotReady => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// A single failure, optionally tied to a request field path such as `signatures[2].pubkey`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    pub code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
 
 #[derive(Debug, Error)]
 pub enum AppError {
+    #[error("{}", .0.message)]
+    Invalid(FieldError),
+    #[error("{}", join_messages(.0))]
+    Validation(Vec<FieldError>),
     #[error("{0}")]
-    BadRequest(String),
+    NotFound(String),
     #[error("Internal server error")]
     Internal,
+    #[error("RPC error: {0}")]
+    Rpc(String),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, msg) = match &self {
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
-            AppError::Internal => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal server error".to_string(),
-            ),
-        };
+impl AppError {
+    /// A client error with a specific code, tied to a request field
+    pub fn invalid(code: ErrorCode, field: &str, message: impl Into<String>) -> Self {
+        AppError::Invalid(FieldError {
+            code,
+            field: Some(field.to_string()),
+            message: message.into(),
+        })
+    }
 
-        let body = serde_json::to_string(&ApiResponse::<()> {
-            success: false,
-            data: None,
-            error: Some(msg),
+    /// A client error with a specific code that concerns the request as a whole
+    pub fn request(code: ErrorCode, message: impl Into<String>) -> Self {
+        AppError::Invalid(FieldError {
+            code,
+            field: None,
+            message: message.into(),
         })
-        .unwrap();
+    }
+
+    pub fn missing(field: &str) -> Self {
+        Self::invalid(
+            ErrorCode::MissingField,
+            field,
+            format!("Missing required field: {}", field),
+        )
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Invalid(error) => error.code,
+            AppError::Validation(_) => ErrorCode::ValidationFailed,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Internal => ErrorCode::Internal,
+            AppError::Rpc(_) => ErrorCode::RpcError,
+        }
+    }
+
+    /// Re-home an error raised for one element of a list, e.g. `Entry 3: ...`
+    /// with its field nested under `signatures[3]`
+    pub fn in_item(self, label: &str, collection: &str, index: usize) -> Self {
+        let path = format!("{}[{}]", collection, index);
+        let nest = |error: FieldError| FieldError {
+            code: error.code,
+            field: Some(match error.field {
+                Some(field) => format!("{}.{}", path, field),
+                None => path.clone(),
+            }),
+            message: format!("{} {}: {}", label, index, error.message),
+        };
+
+        match self {
+            AppError::Invalid(error) => AppError::Invalid(nest(error)),
+            AppError::Validation(errors) => {
+                AppError::Validation(errors.into_iter().map(nest).collect())
+            }
+            other => other,
+        }
+    }
 
-        (status, [("content-type", "application/json")], body).into_response()
+    fn field_errors(self) -> Vec<FieldError> {
+        match self {
+            AppError::Invalid(error) => vec![error],
+            AppError::Validation(errors) => errors,
+            other => vec![FieldError {
+                code: other.code(),
+                field: None,
+                message: other.to_string(),
+            }],
+        }
     }
 }
 
-impl From<JsonRejection> for AppError {
-    fn from(rej: JsonRejection) -> Self {
-        AppError::BadRequest(format!("Invalid JSON: {}", rej))
+fn join_messages(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Collects independent validation failures so they can be reported together
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<AppError>,
+}
+
+impl Validator {
+    /// Record a failed check, returning the value when it passed
+    pub fn check<T>(&mut self, result: Result<T, AppError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.errors.push(error);
+                None
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<(), AppError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.into_error())
+        }
+    }
+
+    /// The collected failures as one error; server-side failures take precedence
+    pub fn into_error(mut self) -> AppError {
+        if let Some(index) = self
+            .errors
+            .iter()
+            .position(|error| error.code().status().is_server_error())
+        {
+            return self.errors.swap_remove(index);
+        }
+
+        if self.errors.len() == 1 {
+            return self.errors.remove(0);
+        }
+
+        AppError::Validation(self.errors.into_iter().flat_map(AppError::field_errors).collect())
     }
-}
\ No newline at end of file
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let code = self.code();
+        let message = self.to_string();
+
+        if code.status().is_server_error() {
+            tracing::error!(?code, error = %message, "request failed");
+        } else {
+            tracing::debug!(?code, error = %message, "request rejected");
+        }
+
+        let mut body = ApiResponse::<()>::error(code, message);
+        body.request_id = telemetry::current_request_id();
+        match self {
+            AppError::Invalid(error) => body.field = error.field,
+            AppError::Validation(errors) => body.errors = Some(errors),
+            _ => {}
+        }
+
+        let body = serde_json::to_string(&body).unwrap();
+
+        let mut response =
+            (code.status(), [("content-type", "application/json")], body).into_response();
+        match code {
+            ErrorCode::Unauthorized => {
+                response
+                    .headers_mut()
+                    .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            }
+            // Shed load is usually gone a moment later
+            ErrorCode::Overloaded => {
+                response
+                    .headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+            }
+            _ => {}
+        }
+        // Lets the metrics middleware count errors by code
+        response.extensions_mut().insert(code);
+        response
+    }
+}
@@ -0,0 +1,269 @@
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{
+        path::ErrorKind,
+        rejection::{BytesRejection, PathRejection, QueryRejection},
+        FromRequest, FromRequestParts, Path, Query, Request,
+    },
+    http::{header::CONTENT_TYPE, request::Parts, StatusCode},
+};
+use serde::de::DeserializeOwned;
+use crate::error::{AppError, ErrorCode};
+
+/// JSON body extractor whose failures are reported in the API error envelope
+///
+/// Unlike `axum::Json`, deserialization errors carry the path of the failing
+/// field, e.g. `signatures[2].pubkey`.
+pub struct ApiJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_json_content_type(&req) {
+            return Err(AppError::request(
+                ErrorCode::UnsupportedMediaType,
+                "Expected request with `Content-Type: application/json`",
+            ));
+        }
+
+        let bytes = Bytes::from_request(req, state).await.map_err(body_rejection)?;
+
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        let value = serde_path_to_error::deserialize(deserializer).map_err(body_error)?;
+
+        Ok(ApiJson(value))
+    }
+}
+
+/// Path parameters whose failures are reported in the API error envelope
+pub struct ApiPath<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ApiPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::from_request_parts(parts, state).await {
+            Ok(Path(value)) => Ok(ApiPath(value)),
+            Err(rejection) => Err(path_rejection(rejection)),
+        }
+    }
+}
+
+/// Query parameters whose failures are reported in the API error envelope
+pub struct ApiQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ApiQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Query::from_request_parts(parts, state).await {
+            Ok(Query(value)) => Ok(ApiQuery(value)),
+            Err(rejection) => Err(query_rejection(rejection)),
+        }
+    }
+}
+
+fn path_rejection(rejection: PathRejection) -> AppError {
+    let message = rejection.body_text();
+    let key = match &rejection {
+        PathRejection::FailedToDeserializePathParams(error) => match error.kind() {
+            ErrorKind::InvalidUtf8InPathParam { key }
+            | ErrorKind::ParseErrorAtKey { key, .. } => Some(key.as_str()),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    match key {
+        Some(key) => AppError::invalid(ErrorCode::BadRequest, key, message),
+        None => AppError::request(ErrorCode::BadRequest, message),
+    }
+}
+
+fn query_rejection(rejection: QueryRejection) -> AppError {
+    AppError::request(ErrorCode::BadRequest, rejection.body_text())
+}
+
+/// Failure to read the request body, e.g. because it exceeds the body limit
+pub fn body_rejection(rejection: BytesRejection) -> AppError {
+    if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        AppError::request(ErrorCode::PayloadTooLarge, "Request body is too large")
+    } else {
+        AppError::request(ErrorCode::BadRequest, rejection.body_text())
+    }
+}
+
+fn has_json_content_type(req: &Request) -> bool {
+    let Some(content_type) = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime == "application/json" || (mime.starts_with("application/") && mime.ends_with("+json"))
+}
+
+fn body_error(error: serde_path_to_error::Error<serde_json::Error>) -> AppError {
+    let path = error.path().to_string();
+    let inner = error.into_inner();
+
+    if !inner.is_data() {
+        return AppError::request(ErrorCode::InvalidJson, format!("Invalid JSON: {}", inner));
+    }
+
+    // serde reports a missing field against its parent, so point at the field itself
+    let message = inner.to_string();
+    let field = match missing_field(&message) {
+        Some(name) if path == "." => name.to_string(),
+        Some(name) => format!("{}.{}", path, name),
+        None if path == "." => {
+            return AppError::request(
+                ErrorCode::InvalidBody,
+                format!("Invalid request body: {}", message),
+            )
+        }
+        None => {
+            return AppError::invalid(
+                ErrorCode::InvalidBody,
+                &path,
+                format!("Invalid request body at {}: {}", path, message),
+            )
+        }
+    };
+
+    AppError::missing(&field)
+}
+
+/// The field named by serde's `missing field` error, which carries no structured kind;
+/// the wording is pinned by the tests below
+fn missing_field(message: &str) -> Option<&str> {
+    message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.split_once('`'))
+        .map(|(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, routing::get, Router};
+    use serde::Deserialize;
+    use tower::Service;
+    use crate::types::ClusterQuery;
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize)]
+    struct Entry {
+        pubkey: String,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Signatures {
+        signatures: Vec<Entry>,
+    }
+
+    async fn extract(content_type: &str, body: &str) -> AppError {
+        let req = Request::builder()
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        ApiJson::<Signatures>::from_request(req, &()).await.map(|_| ()).unwrap_err()
+    }
+
+    fn field_error(error: AppError) -> (ErrorCode, Option<String>) {
+        match error {
+            AppError::Invalid(error) => (error.code, error.field),
+            other => panic!("expected a field error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn serde_still_words_missing_fields_the_same_way() {
+        let error = serde_json::from_str::<Entry>("{}").unwrap_err();
+        assert_eq!(missing_field(&error.to_string()), Some("pubkey"));
+        assert_eq!(missing_field("invalid type: null, expected a string"), None);
+    }
+
+    #[tokio::test]
+    async fn missing_fields_point_at_the_field() {
+        let error = extract("application/json", "{}").await;
+        assert_eq!(field_error(error), (ErrorCode::MissingField, Some("signatures".into())));
+
+        let error = extract("application/json", r#"{"signatures": [{"pubkey": "a"}, {}]}"#).await;
+        let field = Some("signatures[1].pubkey".into());
+        assert_eq!(field_error(error), (ErrorCode::MissingField, field));
+    }
+
+    #[tokio::test]
+    async fn wrong_types_and_bad_json_are_told_apart() {
+        let error = extract("application/json", r#"{"signatures": [{"pubkey": 1}]}"#).await;
+        let field = Some("signatures[0].pubkey".into());
+        assert_eq!(field_error(error), (ErrorCode::InvalidBody, field));
+
+        let error = extract("application/json", "{").await;
+        assert_eq!(error.code(), ErrorCode::InvalidJson);
+
+        let error = extract("text/plain", "{}").await;
+        assert_eq!(error.code(), ErrorCode::UnsupportedMediaType);
+        let error = extract("application/vnd.api+json", "{}").await;
+        assert_eq!(error.code(), ErrorCode::MissingField);
+    }
+
+    #[tokio::test]
+    async fn path_and_query_rejections_use_the_envelope() {
+        async fn handler(
+            ApiPath(pubkey): ApiPath<String>,
+            ApiQuery(query): ApiQuery<ClusterQuery>,
+        ) -> String {
+            format!("{}{}", pubkey, query.cluster.unwrap_or_default())
+        }
+        let mut app = Router::new().route("/account/:pubkey", get(handler));
+
+        let mut envelope = |uri: &str| {
+            let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = app.call(req);
+            async move {
+                let response = response.await.unwrap();
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&bytes).unwrap())
+            }
+        };
+
+        let (status, body) = envelope("/account/%FF").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "BAD_REQUEST");
+        assert_eq!(body["field"], "pubkey");
+
+        let (status, body) = envelope("/account/abc?cluster=a&cluster=b").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["success"], false);
+        assert_eq!(body["code"], "BAD_REQUEST");
+    }
+}
@@ -0,0 +1,172 @@
+use axum::{
+    extract::State,
+    Json,
+};
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use crate::{
+    decode::decode_account_data,
+    error::AppError,
+    extract::{ApiPath, ApiQuery},
+    rpc::{RpcAccount, RpcEndpoint, RpcResponse},
+    state::AppState,
+    types::{AccountInfoResponse, ApiResponse, ClusterQuery, TokenHolding, TokenHoldingsResponse},
+    utils::parse_pubkey,
+};
+
+#[derive(Deserialize)]
+struct RpcKeyedTokenAccount {
+    pubkey: String,
+    account: RpcParsedTokenAccount,
+}
+
+#[derive(Deserialize)]
+struct RpcParsedTokenAccount {
+    owner: String,
+    data: RpcParsedData,
+}
+
+#[derive(Deserialize)]
+struct RpcParsedData {
+    parsed: RpcParsedTokenState,
+}
+
+#[derive(Deserialize)]
+struct RpcParsedTokenState {
+    info: RpcTokenAccountInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTokenAccountInfo {
+    mint: String,
+    state: String,
+    token_amount: RpcTokenAmount,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTokenAmount {
+    amount: String,
+    decimals: u8,
+    ui_amount: Option<f64>,
+    ui_amount_string: String,
+}
+
+/// Fetch an account's balance, metadata and data
+#[utoipa::path(
+    get,
+    path = "/account/{pubkey}",
+    tag = "account",
+    params(("pubkey" = String, Path, description = "Account address"), ClusterQuery),
+    responses((status = 200, body = ApiResponse<AccountInfoResponse>)),
+)]
+pub async fn get_account(
+    State(state): State<AppState>,
+    ApiPath(pubkey): ApiPath<String>,
+    ApiQuery(query): ApiQuery<ClusterQuery>,
+) -> Result<Json<ApiResponse<AccountInfoResponse>>, AppError> {
+    let pubkey = parse_pubkey(&pubkey, "pubkey")?;
+    let rpc = state.rpc.endpoint(query.cluster.as_deref())?;
+
+    let response: RpcResponse<Option<RpcAccount>> = rpc
+        .call(
+            "getAccountInfo",
+            json!([pubkey.to_string(), { "encoding": "base64" }]),
+        )
+        .await?;
+
+    let account = response
+        .value
+        .ok_or_else(|| AppError::NotFound(format!("Account {} not found", pubkey)))?;
+
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(&account.data.0)
+        .map_err(|_| AppError::Rpc("getAccountInfo returned invalid base64 data".to_string()))?;
+    let owner = account
+        .owner
+        .parse::<Pubkey>()
+        .map_err(|_| AppError::Rpc("getAccountInfo returned an invalid owner".to_string()))?;
+
+    let response = AccountInfoResponse {
+        pubkey: pubkey.to_string(),
+        slot: response.context.slot,
+        lamports: account.lamports,
+        owner: account.owner,
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        space: account.space.unwrap_or(data.len() as u64),
+        parsed: decode_account_data(&owner, &data).ok(),
+        data: account.data.0,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// List the SPL Token and Token-2022 accounts held by a wallet
+#[utoipa::path(
+    get,
+    path = "/account/{pubkey}/tokens",
+    tag = "account",
+    params(("pubkey" = String, Path, description = "Wallet address"), ClusterQuery),
+    responses((status = 200, body = ApiResponse<TokenHoldingsResponse>)),
+)]
+pub async fn get_token_holdings(
+    State(state): State<AppState>,
+    ApiPath(owner): ApiPath<String>,
+    ApiQuery(query): ApiQuery<ClusterQuery>,
+) -> Result<Json<ApiResponse<TokenHoldingsResponse>>, AppError> {
+    let owner = parse_pubkey(&owner, "pubkey")?.to_string();
+    let rpc = state.rpc.endpoint(query.cluster.as_deref())?;
+
+    let (token, token_2022) = tokio::try_join!(
+        token_accounts_by_owner(&rpc, &owner, spl_token::id()),
+        token_accounts_by_owner(&rpc, &owner, spl_token_2022::id()),
+    )?;
+
+    let slot = token.context.slot.max(token_2022.context.slot);
+    let tokens = token
+        .value
+        .into_iter()
+        .chain(token_2022.value)
+        .map(|keyed| {
+            let info = keyed.account.data.parsed.info;
+            TokenHolding {
+                address: keyed.pubkey,
+                mint: info.mint,
+                program_id: keyed.account.owner,
+                amount: info.token_amount.amount,
+                decimals: info.token_amount.decimals,
+                ui_amount: info.token_amount.ui_amount,
+                ui_amount_string: info.token_amount.ui_amount_string,
+                state: info.state,
+            }
+        })
+        .collect();
+
+    let response = TokenHoldingsResponse {
+        owner,
+        slot,
+        tokens,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+async fn token_accounts_by_owner(
+    rpc: &RpcEndpoint,
+    owner: &str,
+    program_id: Pubkey,
+) -> Result<RpcResponse<Vec<RpcKeyedTokenAccount>>, AppError> {
+    rpc.call(
+        "getTokenAccountsByOwner",
+        json!([
+            owner,
+            { "programId": program_id.to_string() },
+            { "encoding": "jsonParsed" },
+        ]),
+    )
+    .await
+}
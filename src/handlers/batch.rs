@@ -0,0 +1,260 @@
+use axum::{extract::State, Json};
+use futures::{stream, StreamExt};
+use crate::{
+    config::{Config, MAX_DISPATCH_BATCH_SIZE},
+    dispatch::{self, Caller, Operation, Outcome},
+    error::{AppError, ErrorCode, FieldError, Validator},
+    extract::ApiJson,
+    state::AppState,
+    types::{ApiResponse, BatchOperation, BatchRequest, BatchResponse, BatchResult},
+};
+
+/// Run many operations in one request
+///
+/// Operations run concurrently, each through the same authentication, limits and
+/// validation as its route, and results come back in request order with the route's
+/// status and envelope. With `atomic`, any failure fails the whole batch and every
+/// failed operation is listed in `errors`. `/transaction/send` is not allowed: a batch
+/// cut short by its timeout would lose the signatures of transactions already submitted,
+/// and retrying it could pay twice.
+#[utoipa::path(
+    post,
+    path = "/batch",
+    tag = "batch",
+    request_body = BatchRequest,
+    responses((status = 200, body = ApiResponse<BatchResponse>)),
+)]
+pub async fn run_batch(
+    State(state): State<AppState>,
+    caller: Caller,
+    ApiJson(req): ApiJson<BatchRequest>,
+) -> Result<Json<ApiResponse<BatchResponse>>, AppError> {
+    if req.operations.is_empty() {
+        return Err(AppError::request(
+            ErrorCode::EmptyBatch,
+            "Batch must contain at least one operation",
+        ));
+    }
+
+    if req.operations.len() > MAX_DISPATCH_BATCH_SIZE {
+        return Err(AppError::request(
+            ErrorCode::TooManyItems,
+            format!("Batch exceeds maximum size ({}).", MAX_DISPATCH_BATCH_SIZE),
+        ));
+    }
+
+    let mut validator = Validator::default();
+    for (index, operation) in req.operations.iter().enumerate() {
+        validator.check(validate_batchable(operation, index));
+    }
+    validator.finish()?;
+
+    let concurrency = concurrency(&state.config, &req);
+    let outcomes = stream::iter(req.operations)
+        .map(|operation| run_operation(&state, &caller, operation))
+        .buffered(concurrency)
+        .collect::<Vec<_>>()
+        .await;
+
+    if req.atomic {
+        let mut validator = Validator::default();
+        for (index, (_, outcome)) in outcomes.iter().enumerate() {
+            validator.check(operation_result(outcome, index));
+        }
+        validator.finish()?;
+    }
+
+    let results = outcomes
+        .into_iter()
+        .map(|(route, outcome)| BatchResult {
+            route,
+            success: outcome.is_success(),
+            status: outcome.status.as_u16(),
+            response: outcome.body,
+        })
+        .collect::<Vec<_>>();
+    let succeeded = results.iter().filter(|result| result.success).count();
+
+    let response = BatchResponse {
+        failed: results.len() - succeeded,
+        succeeded,
+        results,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Operations of `req` to run at once: as requested, within what the routes allow
+fn concurrency(config: &Config, req: &BatchRequest) -> usize {
+    let routes = req.operations.iter().map(|operation| operation.route.as_str());
+    let max_concurrency = dispatch::concurrency(config, routes);
+    req.concurrency
+        .unwrap_or(max_concurrency)
+        .clamp(1, max_concurrency)
+}
+
+async fn run_operation(
+    state: &AppState,
+    caller: &Caller,
+    operation: BatchOperation,
+) -> (String, Outcome) {
+    let outcome = match Operation::at(&operation.route).filter(|op| op.enabled(&state.config)) {
+        Some(op) => state.dispatcher.call(op, operation.body, caller).await,
+        None => {
+            let message = format!("No operation for route {}", operation.route);
+            Outcome::error(AppError::NotFound(message)).await
+        }
+    };
+
+    (operation.route, outcome)
+}
+
+fn validate_batchable(operation: &BatchOperation, index: usize) -> Result<(), AppError> {
+    if operation.route != "/transaction/send" {
+        return Ok(());
+    }
+
+    Err(AppError::invalid(
+        ErrorCode::InvalidValue,
+        &format!("operations[{}].route", index),
+        "/transaction/send is not allowed in a batch; send transactions one at a time",
+    ))
+}
+
+/// A failed operation as an error nested under `operations[index]`
+fn operation_result(outcome: &Outcome, index: usize) -> Result<(), AppError> {
+    if outcome.is_success() {
+        return Ok(());
+    }
+
+    let envelope = &outcome.body;
+    if let Ok(errors) = serde_json::from_value::<Vec<FieldError>>(envelope["errors"].clone()) {
+        return Err(AppError::Validation(errors).in_item("Operation", "operations", index));
+    }
+
+    let error = FieldError {
+        code: outcome.code.unwrap_or(ErrorCode::Internal),
+        field: envelope["field"].as_str().map(str::to_string),
+        message: envelope["error"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| outcome.status.to_string()),
+    };
+
+    Err(AppError::Invalid(error).in_item("Operation", "operations", index))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use crate::{
+        config::{RouteLimitsOverride, DISPATCH_CONCURRENCY},
+        testing::{self, TestResponse},
+    };
+    use super::*;
+
+    async fn batch(body: Value) -> TestResponse {
+        let app = testing::app(Config::default());
+        testing::call(&app, testing::post("/batch", body)).await
+    }
+
+    fn rent(data_len: u64) -> Value {
+        json!({ "route": "/calc/rent", "body": { "data_len": data_len } })
+    }
+
+    #[tokio::test]
+    async fn results_keep_request_order() {
+        let operations: Vec<_> = (0..20).map(|data_len| rent(data_len * 100)).collect();
+        let response = batch(json!({ "operations": operations, "concurrency": 8 })).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        let results = response.body["data"]["results"].as_array().unwrap();
+        let lamports: Vec<_> = results
+            .iter()
+            .map(|result| result["response"]["data"]["lamports"].as_u64().unwrap())
+            .collect();
+        assert_eq!(lamports.len(), 20);
+        assert!(lamports.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", lamports);
+    }
+
+    #[tokio::test]
+    async fn atomic_batches_list_every_failure() {
+        let bad_pubkey = json!({ "route": "/account/:pubkey", "body": { "pubkey": "nope" } });
+        let operations = json!([rent(0), rent(u64::MAX), rent(1), bad_pubkey]);
+
+        let response = batch(json!({ "operations": operations, "atomic": true })).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", response.body);
+        let errors = response.body["errors"].as_array().unwrap();
+        let fields: Vec<_> = errors.iter().map(|error| error["field"].as_str().unwrap()).collect();
+        assert_eq!(fields, ["operations[1].data_len", "operations[3].pubkey"]);
+        assert_eq!(errors[0]["code"], "INVALID_LENGTH");
+        assert!(response.body["data"].is_null());
+
+        // Without `atomic` the same batch succeeds and reports the failures per operation
+        let response = batch(json!({ "operations": operations })).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["data"]["succeeded"], 2);
+        assert_eq!(response.body["data"]["failed"], 2);
+    }
+
+    #[tokio::test]
+    async fn unknown_routes_fail_only_their_operation() {
+        let unknown = json!({ "route": "/nope", "body": {} });
+        let response = batch(json!({ "operations": [rent(0), unknown] })).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        let results = &response.body["data"]["results"];
+        assert_eq!(results[0]["success"], true);
+        assert_eq!(results[1]["success"], false);
+        assert_eq!(results[1]["status"], 404);
+        assert_eq!(results[1]["response"]["code"], "NOT_FOUND");
+    }
+
+    #[test]
+    fn concurrency_is_clamped_to_what_the_routes_allow() {
+        let mut config = Config::default();
+        config.limits.routes.push(RouteLimitsOverride {
+            route: "/calc/rent".to_string(),
+            max_concurrent: Some(3),
+            ..RouteLimitsOverride::default()
+        });
+        let request = |route: &str, concurrency: Option<usize>| {
+            let operations = json!([{ "route": "/calc/fee" }, { "route": route }]);
+            let body = json!({ "operations": operations, "concurrency": concurrency });
+            serde_json::from_value::<BatchRequest>(body).unwrap()
+        };
+
+        assert_eq!(concurrency(&config, &request("/calc/rent", None)), 3);
+        assert_eq!(concurrency(&config, &request("/calc/rent", Some(100))), 3);
+        assert_eq!(concurrency(&config, &request("/calc/rent", Some(0))), 1);
+        assert_eq!(concurrency(&config, &request("/calc/rent", Some(2))), 2);
+        let unlimited = request("/send/sol", Some(100));
+        assert_eq!(concurrency(&config, &unlimited), DISPATCH_CONCURRENCY);
+    }
+
+    #[tokio::test]
+    async fn transactions_cannot_be_sent_in_a_batch() {
+        let app = testing::app(Config::default());
+        let send = json!({ "route": "/transaction/send", "body": { "transaction": "" } });
+        let rent = json!({ "route": "/calc/rent", "body": { "data_len": 0 } });
+
+        for atomic in [false, true] {
+            let batch = json!({ "operations": [rent, send], "atomic": atomic });
+            let response = testing::call(&app, testing::post("/batch", batch)).await;
+            assert_eq!(response.status, StatusCode::BAD_REQUEST);
+            assert_eq!(response.body["code"], "INVALID_VALUE");
+            assert_eq!(response.body["field"], "operations[1].route");
+        }
+
+        let calls = json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "calc_rent", "params": { "data_len": 0 } },
+            { "jsonrpc": "2.0", "id": 2, "method": "send_transaction", "params": {} },
+        ]);
+        let response = testing::call(&app, testing::post("/rpc", calls)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body[0]["result"].is_object());
+        assert_eq!(response.body[1]["error"]["code"], -32600);
+        assert_eq!(response.body[1]["error"]["data"]["code"], "INVALID_VALUE");
+    }
+}
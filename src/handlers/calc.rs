@@ -0,0 +1,390 @@
+use axum::Json;
+use solana_sdk::{
+    compute_budget, ed25519_program,
+    nonce::state::State as NonceState,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::{Rent, ACCOUNT_STORAGE_OVERHEAD},
+    secp256k1_program,
+    stake::state::StakeStateV2,
+    system_instruction::MAX_PERMITTED_DATA_LENGTH,
+};
+use spl_token_2022::{
+    extension::{AccountType, ExtensionType},
+    state::{Account, Mint, Multisig},
+};
+use crate::{
+    config::{
+        DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT, DEFAULT_LAMPORTS_PER_SIGNATURE,
+        MAX_COMPUTE_UNIT_LIMIT,
+    },
+    error::{AppError, ErrorCode},
+    extract::ApiJson,
+    types::{
+        ApiResponse, FeeCalcRequest, FeeCalcResponse, RentCalcRequest, RentCalcResponse,
+        RentConfig,
+    },
+    utils::parse_message,
+};
+
+/// Program id of the secp256r1 signature verification precompile
+const SECP256R1_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("Secp256r1SigVerify1111111111111111111111111");
+
+/// Rent-exempt minimum balance for a data length or a well-known account shape
+#[utoipa::path(
+    post,
+    path = "/calc/rent",
+    tag = "calc",
+    request_body = RentCalcRequest,
+    responses((status = 200, body = ApiResponse<RentCalcResponse>)),
+)]
+pub async fn calc_rent(
+    ApiJson(req): ApiJson<RentCalcRequest>,
+) -> Result<Json<ApiResponse<RentCalcResponse>>, AppError> {
+    let data_len = match (req.data_len, req.account.as_deref()) {
+        (Some(_), Some(_)) => {
+            return Err(AppError::invalid(
+                ErrorCode::ConflictingFields,
+                "account",
+                "Provide either data_len or account, not both",
+            ))
+        }
+        (Some(data_len), None) => {
+            if !req.extensions.is_empty() {
+                return Err(AppError::invalid(
+                    ErrorCode::ConflictingFields,
+                    "extensions",
+                    "extensions require a token_2022_mint or token_2022_account shortcut",
+                ));
+            }
+            data_len
+        }
+        (None, Some(account)) => shortcut_len(account, &req.extensions)? as u64,
+        (None, None) => {
+            return Err(AppError::invalid(
+                ErrorCode::MissingField,
+                "data_len",
+                "Missing required field: data_len or account",
+            ))
+        }
+    };
+
+    let rent = match req.rent {
+        Some(config) => {
+            if !config.exemption_threshold.is_finite() || config.exemption_threshold < 0.0 {
+                return Err(AppError::invalid(
+                    ErrorCode::InvalidValue,
+                    "rent.exemption_threshold",
+                    "Invalid exemption_threshold: must be a non-negative number",
+                ));
+            }
+            if config.burn_percent > 100 {
+                return Err(AppError::invalid(
+                    ErrorCode::InvalidValue,
+                    "rent.burn_percent",
+                    "Invalid burn_percent: must be at most 100",
+                ));
+            }
+            Rent {
+                lamports_per_byte_year: config.lamports_per_byte_year,
+                exemption_threshold: config.exemption_threshold,
+                burn_percent: config.burn_percent,
+            }
+        }
+        None => Rent::default(),
+    };
+
+    if data_len > MAX_PERMITTED_DATA_LENGTH {
+        return Err(AppError::invalid(
+            ErrorCode::InvalidLength,
+            "data_len",
+            format!(
+                "data_len exceeds the maximum account size ({} bytes)",
+                MAX_PERMITTED_DATA_LENGTH
+            ),
+        ));
+    }
+
+    let response = RentCalcResponse {
+        data_len,
+        lamports: minimum_balance(&rent, data_len)?,
+        rent: RentConfig {
+            lamports_per_byte_year: rent.lamports_per_byte_year,
+            exemption_threshold: rent.exemption_threshold,
+            burn_percent: rent.burn_percent,
+        },
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Signature and prioritization fees a message will be charged
+#[utoipa::path(
+    post,
+    path = "/calc/fee",
+    tag = "calc",
+    request_body = FeeCalcRequest,
+    responses((status = 200, body = ApiResponse<FeeCalcResponse>)),
+)]
+pub async fn calc_fee(
+    ApiJson(req): ApiJson<FeeCalcRequest>,
+) -> Result<Json<ApiResponse<FeeCalcResponse>>, AppError> {
+    let message = parse_message(&req.message)?;
+    let lamports_per_signature = req
+        .lamports_per_signature
+        .unwrap_or(DEFAULT_LAMPORTS_PER_SIGNATURE);
+
+    let account_keys = message.static_account_keys();
+    let mut signatures = u64::from(message.header().num_required_signatures);
+    let mut requested_limit = None;
+    let mut compute_unit_price = 0u64;
+    let mut other_instructions = 0u32;
+
+    for (index, instruction) in message.instructions().iter().enumerate() {
+        let program_id = instruction.program_id(account_keys);
+        let data = &instruction.data;
+
+        if *program_id == ed25519_program::id()
+            || *program_id == secp256k1_program::id()
+            || *program_id == SECP256R1_PROGRAM_ID
+        {
+            // Precompiles charge for every signature they verify
+            signatures += u64::from(data.first().copied().unwrap_or(0));
+        }
+
+        if *program_id != compute_budget::id() {
+            other_instructions += 1;
+            continue;
+        }
+
+        let invalid = || {
+            AppError::invalid(
+                ErrorCode::InvalidMessage,
+                "message",
+                format!("Instruction {}: Invalid compute budget instruction", index),
+            )
+        };
+        match data.first() {
+            Some(2) => {
+                let bytes = data.get(1..5).ok_or_else(invalid)?;
+                requested_limit = Some(u32::from_le_bytes(bytes.try_into().unwrap()));
+            }
+            Some(3) => {
+                let bytes = data.get(1..9).ok_or_else(invalid)?;
+                compute_unit_price = u64::from_le_bytes(bytes.try_into().unwrap());
+            }
+            Some(1) | Some(4) => {}
+            _ => return Err(invalid()),
+        }
+    }
+
+    let default_limit = other_instructions.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT);
+    let compute_unit_limit = requested_limit
+        .unwrap_or(default_limit)
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+
+    // The price is in micro-lamports per compute unit, rounded up to whole lamports
+    let prioritization_fee = (u128::from(compute_unit_price) * u128::from(compute_unit_limit))
+        .div_ceil(1_000_000);
+    let prioritization_fee = u64::try_from(prioritization_fee).unwrap_or(u64::MAX);
+
+    let base_fee = signatures.saturating_mul(lamports_per_signature);
+
+    let response = FeeCalcResponse {
+        signatures,
+        lamports_per_signature,
+        base_fee,
+        compute_unit_limit,
+        compute_unit_price,
+        prioritization_fee,
+        total_fee: base_fee.saturating_add(prioritization_fee),
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// `Rent::minimum_balance`, which multiplies unchecked, with the overflow reported
+fn minimum_balance(rent: &Rent, data_len: u64) -> Result<u64, AppError> {
+    let byte_years = (ACCOUNT_STORAGE_OVERHEAD + data_len)
+        .checked_mul(rent.lamports_per_byte_year)
+        .ok_or_else(|| {
+            AppError::invalid(
+                ErrorCode::AmountOutOfRange,
+                "rent.lamports_per_byte_year",
+                "lamports_per_byte_year is too large for this data_len",
+            )
+        })?;
+
+    Ok((byte_years as f64 * rent.exemption_threshold) as u64)
+}
+
+fn shortcut_len(account: &str, extension_names: &[String]) -> Result<usize, AppError> {
+    let base_len = match account {
+        "mint" => Mint::LEN,
+        "token_account" => Account::LEN,
+        "multisig" => Multisig::LEN,
+        "nonce" => NonceState::size(),
+        "stake" => StakeStateV2::size_of(),
+        "token_2022_mint" | "token_2022_account" => {
+            return token_2022_len(account == "token_2022_mint", extension_names)
+        }
+        other => {
+            return Err(AppError::invalid(
+                ErrorCode::InvalidValue,
+                "account",
+                format!(
+                    "Unknown account shortcut: {} (expected mint, token_account, multisig, \
+                     nonce, stake, token_2022_mint or token_2022_account)",
+                    other
+                ),
+            ))
+        }
+    };
+
+    if !extension_names.is_empty() {
+        return Err(AppError::invalid(
+            ErrorCode::ConflictingFields,
+            "extensions",
+            format!("extensions are not supported for {} accounts", account),
+        ));
+    }
+
+    Ok(base_len)
+}
+
+fn token_2022_len(is_mint: bool, extension_names: &[String]) -> Result<usize, AppError> {
+    let expected = if is_mint {
+        AccountType::Mint
+    } else {
+        AccountType::Account
+    };
+
+    let extensions = extension_names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let field = format!("extensions[{}]", index);
+            let extension = parse_extension_type(name, &field)?;
+            if extension.get_account_type() != expected {
+                return Err(AppError::invalid(
+                    ErrorCode::ConflictingFields,
+                    &field,
+                    format!("Extension {} does not apply to {:?} accounts", name, expected),
+                ));
+            }
+            Ok(extension)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let len = if is_mint {
+        ExtensionType::try_calculate_account_len::<Mint>(&extensions)
+    } else {
+        ExtensionType::try_calculate_account_len::<Account>(&extensions)
+    };
+
+    len.map_err(|_| {
+        AppError::invalid(
+            ErrorCode::InvalidValue,
+            "extensions",
+            "Variable-length extensions such as TokenMetadata cannot be sized by name; \
+             pass data_len instead",
+        )
+    })
+}
+
+/// Look up an extension by the name used in decoded account data
+fn parse_extension_type(name: &str, field: &str) -> Result<ExtensionType, AppError> {
+    (1..=u16::MAX)
+        .map_while(|value| ExtensionType::try_from(value).ok())
+        .find(|extension| format!("{:?}", extension) == name)
+        .ok_or_else(|| {
+            AppError::invalid(
+                ErrorCode::InvalidValue,
+                field,
+                format!("Unknown Token-2022 extension: {}", name),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use solana_sdk::{
+        instruction::Instruction, message::Message, message::VersionedMessage, pubkey::Pubkey,
+    };
+    use super::*;
+
+    fn rent_request(data_len: u64, lamports_per_byte_year: u64) -> RentCalcRequest {
+        RentCalcRequest {
+            data_len: Some(data_len),
+            account: None,
+            extensions: Vec::new(),
+            rent: Some(RentConfig {
+                lamports_per_byte_year,
+                exemption_threshold: 2.0,
+                burn_percent: 50,
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn rent_matches_the_sdk_within_range() {
+        let Json(response) = calc_rent(ApiJson(rent_request(165, 3_480))).await.unwrap();
+        assert_eq!(response.data.unwrap().lamports, Rent::default().minimum_balance(165));
+    }
+
+    #[tokio::test]
+    async fn rent_rejects_oversized_data_len() {
+        let req = rent_request(MAX_PERMITTED_DATA_LENGTH + 1, 3_480);
+        let Err(AppError::Invalid(error)) = calc_rent(ApiJson(req)).await else {
+            panic!("expected a field error");
+        };
+        assert_eq!(error.code, ErrorCode::InvalidLength);
+        assert_eq!(error.field.as_deref(), Some("data_len"));
+    }
+
+    #[tokio::test]
+    async fn rent_reports_overflow_instead_of_wrapping() {
+        let req = rent_request(MAX_PERMITTED_DATA_LENGTH, u64::MAX / 1_000);
+        let Err(AppError::Invalid(error)) = calc_rent(ApiJson(req)).await else {
+            panic!("expected a field error");
+        };
+        assert_eq!(error.code, ErrorCode::AmountOutOfRange);
+        assert_eq!(error.field.as_deref(), Some("rent.lamports_per_byte_year"));
+    }
+
+    #[tokio::test]
+    async fn rent_points_at_the_bad_extension() {
+        let req = RentCalcRequest {
+            data_len: None,
+            account: Some("token_2022_mint".to_string()),
+            extensions: vec!["MintCloseAuthority".to_string(), "Bogus".to_string()],
+            rent: None,
+        };
+        let Err(AppError::Invalid(error)) = calc_rent(ApiJson(req)).await else {
+            panic!("expected a field error");
+        };
+        assert_eq!(error.code, ErrorCode::InvalidValue);
+        assert_eq!(error.field.as_deref(), Some("extensions[1]"));
+    }
+
+    #[tokio::test]
+    async fn fee_counts_precompile_signatures() {
+        let instructions = [ed25519_program::id(), secp256k1_program::id(), SECP256R1_PROGRAM_ID]
+            .into_iter()
+            .map(|program_id| Instruction::new_with_bytes(program_id, &[2, 0], Vec::new()))
+            .collect::<Vec<_>>();
+        let message = Message::new(&instructions, Some(&Pubkey::new_unique()));
+        let bytes = bincode::serialize(&VersionedMessage::Legacy(message)).unwrap();
+        let req = FeeCalcRequest {
+            message: base64::engine::general_purpose::STANDARD.encode(bytes),
+            lamports_per_signature: None,
+        };
+
+        let Json(response) = calc_fee(ApiJson(req)).await.unwrap();
+        let response = response.data.unwrap();
+        assert_eq!(response.signatures, 1 + 3 * 2);
+        assert_eq!(response.base_fee, 7 * DEFAULT_LAMPORTS_PER_SIGNATURE);
+    }
+}
@@ -0,0 +1,58 @@
+use axum::{
+    extract::State,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use crate::{
+    error::AppError,
+    extract::ApiQuery,
+    state::AppState,
+    types::{ApiResponse, ClusterInfoResponse, ClusterQuery},
+};
+
+#[derive(Deserialize)]
+struct RpcVersion {
+    #[serde(rename = "solana-core")]
+    solana_core: String,
+    #[serde(rename = "feature-set")]
+    feature_set: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcEpochInfo {
+    absolute_slot: u64,
+    block_height: u64,
+    epoch: u64,
+}
+
+/// Report the version and current position of the configured cluster
+#[utoipa::path(
+    get,
+    path = "/cluster/info",
+    tag = "cluster",
+    params(ClusterQuery),
+    responses((status = 200, body = ApiResponse<ClusterInfoResponse>)),
+)]
+pub async fn get_cluster_info(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<ClusterQuery>,
+) -> Result<Json<ApiResponse<ClusterInfoResponse>>, AppError> {
+    let rpc = state.rpc.endpoint(query.cluster.as_deref())?;
+
+    let (version, epoch_info) = tokio::try_join!(
+        rpc.call::<RpcVersion>("getVersion", json!([])),
+        rpc.call::<RpcEpochInfo>("getEpochInfo", json!([])),
+    )?;
+
+    let response = ClusterInfoResponse {
+        solana_core: version.solana_core,
+        feature_set: version.feature_set,
+        slot: epoch_info.absolute_slot,
+        block_height: epoch_info.block_height,
+        epoch: epoch_info.epoch,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
@@ -0,0 +1,29 @@
+use axum::Json;
+use crate::{
+    decode::decode_account_data,
+    error::AppError,
+    extract::ApiJson,
+    types::{ApiResponse, DecodeAccountRequest, ParsedAccountData},
+    utils::{decode_base64, parse_pubkey, validate_not_empty},
+};
+
+/// Decode raw account data without touching the cluster
+#[utoipa::path(
+    post,
+    path = "/decode/account",
+    tag = "decode",
+    request_body = DecodeAccountRequest,
+    responses((status = 200, body = ApiResponse<ParsedAccountData>)),
+)]
+pub async fn decode_account(
+    ApiJson(req): ApiJson<DecodeAccountRequest>,
+) -> Result<Json<ApiResponse<ParsedAccountData>>, AppError> {
+    validate_not_empty(&req.owner, "owner")?;
+
+    let owner = parse_pubkey(&req.owner, "owner")?;
+    let data = decode_base64(&req.data, "data")?;
+
+    let parsed = decode_account_data(&owner, &data)?;
+
+    Ok(Json(ApiResponse::success(parsed)))
+}
@@ -0,0 +1,95 @@
+use axum::Json;
+use solana_sdk::secp256k1_instruction::construct_eth_pubkey;
+use crate::{
+    error::{AppError, ErrorCode},
+    extract::ApiJson,
+    types::{ApiResponse, EthAddressRequest, EthAddressResponse, Secret},
+    utils::{encode_eth_address, parse_secp256k1_public_key, parse_secp256k1_secret_key},
+};
+
+/// Derive an Ethereum address from a secp256k1 secret or public key
+#[utoipa::path(
+    post,
+    path = "/ethereum/address",
+    tag = "ethereum",
+    request_body = EthAddressRequest,
+    responses((status = 200, body = ApiResponse<EthAddressResponse>)),
+)]
+pub async fn derive_eth_address(
+    ApiJson(req): ApiJson<EthAddressRequest>,
+) -> Result<Json<ApiResponse<EthAddressResponse>>, AppError> {
+    let public_key = match (req.secret.as_ref().map(Secret::expose), req.public_key.as_deref()) {
+        (Some(secret), None) => {
+            libsecp256k1::PublicKey::from_secret_key(&parse_secp256k1_secret_key(secret)?)
+        }
+        (None, Some(public_key)) => parse_secp256k1_public_key(public_key)?,
+        _ => {
+            return Err(AppError::request(
+                ErrorCode::ConflictingFields,
+                "Exactly one of secret or public_key is required",
+            ))
+        }
+    };
+
+    let response = EthAddressResponse {
+        eth_address: encode_eth_address(&construct_eth_pubkey(&public_key)),
+        public_key: format!("0x{}", hex::encode(public_key.serialize())),
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::keypair::generate_secp256k1_keypair;
+    use super::*;
+
+    async fn derive(req: EthAddressRequest) -> EthAddressResponse {
+        let Json(response) = derive_eth_address(ApiJson(req)).await.unwrap();
+        response.data.unwrap()
+    }
+
+    #[tokio::test]
+    async fn generated_keypairs_derive_their_own_address() {
+        let Json(keypair) = generate_secp256k1_keypair().await.unwrap();
+        let keypair = keypair.data.unwrap();
+
+        let from_secret = derive(EthAddressRequest {
+            secret: Some(serde_json::from_value(keypair.secret.clone().into()).unwrap()),
+            public_key: None,
+        })
+        .await;
+        let from_public_key = derive(EthAddressRequest {
+            secret: None,
+            public_key: Some(keypair.public_key.clone()),
+        })
+        .await;
+
+        for response in [from_secret, from_public_key] {
+            assert_eq!(response.eth_address, keypair.eth_address);
+            assert_eq!(response.public_key, keypair.public_key);
+        }
+    }
+
+    #[tokio::test]
+    async fn known_key_derives_the_known_address() {
+        // The secret key 1 is the generator point, whose address is well known
+        let secret = format!("0x{}", "0".repeat(63) + "1");
+        let response = derive(EthAddressRequest {
+            secret: Some(serde_json::from_value(secret.into()).unwrap()),
+            public_key: None,
+        })
+        .await;
+        assert_eq!(response.eth_address, "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf");
+    }
+
+    #[tokio::test]
+    async fn exactly_one_key_is_required() {
+        let req = EthAddressRequest {
+            secret: None,
+            public_key: None,
+        };
+        let error = derive_eth_address(ApiJson(req)).await.err().unwrap();
+        assert_eq!(error.code(), ErrorCode::ConflictingFields);
+    }
+}
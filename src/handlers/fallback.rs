@@ -0,0 +1,15 @@
+use axum::http::{Method, Uri};
+use crate::error::{AppError, ErrorCode};
+
+/// Unknown paths
+pub async fn not_found(uri: Uri) -> AppError {
+    AppError::NotFound(format!("No route for {}", uri.path()))
+}
+
+/// Known paths requested with an unsupported method
+pub async fn method_not_allowed(method: Method, uri: Uri) -> AppError {
+    AppError::request(
+        ErrorCode::MethodNotAllowed,
+        format!("Method {} is not allowed for {}", method, uri.path()),
+    )
+}
@@ -1,7 +1,159 @@
-use axum::Json;
-use crate::{error::AppError, types::ApiResponse};
+use std::time::{Duration, Instant};
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::json;
+use crate::{
+    config::HEALTH_CHECK_TIMEOUT_SECS,
+    error::{AppError, ErrorCode},
+    state::AppState,
+    telemetry,
+    types::{ApiResponse, HealthCheck, HealthReport, HealthStatus},
+};
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, body = ApiResponse<String>)),
+)]
 pub async fn health_check() -> Result<Json<ApiResponse<&'static str>>, AppError> {
     Ok(Json(ApiResponse::success("Solana HTTP server is running!")))
-}
\ No newline at end of file
+}
+
+/// Liveness: the process is up and serving requests, even while draining
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses((status = 200, body = ApiResponse<HealthReport>)),
+)]
+pub async fn liveness(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<HealthReport>>, AppError> {
+    Ok(Json(ApiResponse::success(report(&state, HealthStatus::Ok, Vec::new()))))
+}
+
+/// Readiness: dependencies are reachable and shutdown has not begun
+///
+/// Fails with 503 and `NOT_READY`, still carrying the report in `data`.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, body = ApiResponse<HealthReport>),
+        (status = 503, description = "Not ready", body = ApiResponse<HealthReport>),
+    ),
+)]
+pub async fn readiness(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ApiResponse<HealthReport>>) {
+    let checks = rpc_check(&state).await.into_iter().collect::<Vec<_>>();
+    let failed = checks
+        .iter()
+        .filter_map(|check| Some(format!("{}: {}", check.name, check.error.as_deref()?)))
+        .collect::<Vec<_>>();
+
+    let (status, message) = if state.lifecycle.is_draining() {
+        (HealthStatus::Draining, "Shutting down".to_string())
+    } else if !failed.is_empty() {
+        (HealthStatus::Unavailable, failed.join("; "))
+    } else {
+        let report = report(&state, HealthStatus::Ok, checks);
+        return (StatusCode::OK, Json(ApiResponse::success(report)));
+    };
+
+    let code = ErrorCode::NotReady;
+    let mut body = ApiResponse::error(code, message);
+    body.data = Some(report(&state, status, checks));
+    body.request_id = telemetry::current_request_id();
+    (code.status(), Json(body))
+}
+
+fn report(state: &AppState, status: HealthStatus, checks: Vec<HealthCheck>) -> HealthReport {
+    HealthReport {
+        status,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: env!("GIT_COMMIT").to_string(),
+        uptime_secs: state.lifecycle.started.elapsed().as_secs(),
+        features: state
+            .config
+            .enabled_features()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        checks,
+    }
+}
+
+/// `getHealth` against the default RPC endpoint; skipped when none is configured
+async fn rpc_check(state: &AppState) -> Option<HealthCheck> {
+    let endpoint = state.rpc.optional_endpoint(None).ok()??;
+
+    let started = Instant::now();
+    let timeout = Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS);
+    let call = endpoint.call::<String>("getHealth", json!([]));
+    let error = match tokio::time::timeout(timeout, call).await {
+        Ok(Ok(_)) => None,
+        Ok(Err(err)) => Some(err.to_string()),
+        Err(_) => Some(format!("no response within {} s", HEALTH_CHECK_TIMEOUT_SECS)),
+    };
+
+    Some(HealthCheck {
+        name: "rpc".to_string(),
+        ok: error.is_none(),
+        latency_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use axum::Router;
+    use crate::{config::Config, routes, testing};
+    use super::*;
+
+    fn app(config: Config) -> (Router, AppState) {
+        let state = AppState::new(config, None);
+        (routes::app(&state), state)
+    }
+
+    async fn status(app: &Router, uri: &str) -> StatusCode {
+        testing::call(app, testing::get(uri)).await.status
+    }
+
+    #[tokio::test]
+    async fn readiness_fails_when_the_rpc_is_unreachable() {
+        // Nothing listens on a port once its listener is dropped
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut config = Config::default();
+        config.rpc.url = Some(format!("http://127.0.0.1:{}", port));
+        let (app, _) = app(config);
+
+        let response = testing::call(&app, testing::get("/health/ready")).await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.body["code"], "NOT_READY");
+        assert_eq!(response.body["data"]["status"], "unavailable");
+        let check = &response.body["data"]["checks"][0];
+        assert_eq!(check["name"], "rpc");
+        assert_eq!(check["ok"], false);
+        assert!(check["error"].is_string());
+
+        assert_eq!(status(&app, "/health/live").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn draining_fails_readiness_but_not_liveness() {
+        let (app, state) = app(Config::default());
+        assert_eq!(status(&app, "/health/ready").await, StatusCode::OK);
+        assert_eq!(status(&app, "/health/live").await, StatusCode::OK);
+
+        state.lifecycle.start_draining();
+        let response = testing::call(&app, testing::get("/health/ready")).await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.body["code"], "NOT_READY");
+        assert_eq!(response.body["data"]["status"], "draining");
+        assert_eq!(status(&app, "/health/live").await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,514 @@
+use axum::Json;
+use base64::Engine;
+use sha3::{Digest, Keccak256};
+use solana_sdk::{
+    ed25519_instruction::{SIGNATURE_OFFSETS_SERIALIZED_SIZE, SIGNATURE_OFFSETS_START},
+    secp256k1_instruction::{
+        construct_eth_pubkey,
+        SIGNATURE_OFFSETS_SERIALIZED_SIZE as SECP256K1_SIGNATURE_OFFSETS_SERIALIZED_SIZE,
+    },
+    signature::Signer,
+};
+use crate::{
+    error::{AppError, ErrorCode},
+    extract::ApiJson,
+    telemetry,
+    types::{
+        ApiResponse, Ed25519InstructionRequest, Ed25519InstructionResponse,
+        Ed25519SignatureEntry, Secp256k1InstructionRequest, Secp256k1InstructionResponse,
+        Secp256k1Offsets, Secp256k1SignatureEntry, Secret, SignatureOffsets,
+    },
+    utils::{
+        parse_eth_address, parse_hex, parse_pubkey, parse_secp256k1_secret_key,
+        parse_secret_key, parse_signature, validate_not_empty,
+    },
+};
+
+/// Instruction index the Ed25519 program reads as "this instruction"
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Where a precompile finds the public key, signature and message for one check
+enum SignatureSource<O> {
+    Inline {
+        pubkey: Vec<u8>,
+        signature: Vec<u8>,
+        message: Vec<u8>,
+    },
+    Reference(O),
+}
+
+/// Build an Ed25519 program instruction that verifies one or more signatures
+#[utoipa::path(
+    post,
+    path = "/instruction/ed25519",
+    tag = "instruction",
+    request_body = Ed25519InstructionRequest,
+    responses((status = 200, body = ApiResponse<Ed25519InstructionResponse>)),
+)]
+pub async fn build_ed25519_instruction(
+    ApiJson(req): ApiJson<Ed25519InstructionRequest>,
+) -> Result<Json<ApiResponse<Ed25519InstructionResponse>>, AppError> {
+    let sources = resolve_entries(&req.signatures, resolve_ed25519_entry)?;
+    let (data, offsets) = build_offsets_instruction_data(sources)?;
+
+    let response = Ed25519InstructionResponse {
+        program_id: solana_sdk::ed25519_program::id().to_string(),
+        accounts: vec![],
+        instruction_data: base64::engine::general_purpose::STANDARD.encode(&data),
+        offsets,
+    };
+
+    telemetry::record_instruction_built(&response.program_id);
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Build a Secp256k1 program instruction that verifies one or more recoverable signatures
+#[utoipa::path(
+    post,
+    path = "/instruction/secp256k1",
+    tag = "instruction",
+    request_body = Secp256k1InstructionRequest,
+    responses((status = 200, body = ApiResponse<Secp256k1InstructionResponse>)),
+)]
+pub async fn build_secp256k1_instruction(
+    ApiJson(req): ApiJson<Secp256k1InstructionRequest>,
+) -> Result<Json<ApiResponse<Secp256k1InstructionResponse>>, AppError> {
+    let sources = resolve_entries(&req.signatures, resolve_secp256k1_entry)?;
+
+    // Header is [num_signatures] followed by one offsets struct per signature;
+    // inline eth addresses, signatures (with recovery id) and messages follow.
+    let header_len = 1 + sources.len() * SECP256K1_SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+    let mut payload = Vec::new();
+    let mut offsets = Vec::with_capacity(sources.len());
+
+    for source in sources {
+        let entry_offsets = match source {
+            SignatureSource::Reference(offsets) => offsets,
+            SignatureSource::Inline {
+                pubkey: eth_address,
+                signature,
+                message,
+            } => {
+                let eth_address_offset = data_offset(header_len + payload.len())?;
+                payload.extend_from_slice(&eth_address);
+                let signature_offset = data_offset(header_len + payload.len())?;
+                payload.extend_from_slice(&signature);
+                let message_data_offset = data_offset(header_len + payload.len())?;
+                let message_data_size = data_offset(message.len())?;
+                payload.extend_from_slice(&message);
+                ensure_fits_u16(header_len + payload.len())?;
+
+                Secp256k1Offsets {
+                    signature_offset,
+                    signature_instruction_index: req.instruction_index,
+                    eth_address_offset,
+                    eth_address_instruction_index: req.instruction_index,
+                    message_data_offset,
+                    message_data_size,
+                    message_instruction_index: req.instruction_index,
+                }
+            }
+        };
+        offsets.push(entry_offsets);
+    }
+
+    let mut data = Vec::with_capacity(header_len + payload.len());
+    data.push(offsets.len() as u8);
+    for entry_offsets in &offsets {
+        data.extend_from_slice(&entry_offsets.signature_offset.to_le_bytes());
+        data.push(entry_offsets.signature_instruction_index);
+        data.extend_from_slice(&entry_offsets.eth_address_offset.to_le_bytes());
+        data.push(entry_offsets.eth_address_instruction_index);
+        data.extend_from_slice(&entry_offsets.message_data_offset.to_le_bytes());
+        data.extend_from_slice(&entry_offsets.message_data_size.to_le_bytes());
+        data.push(entry_offsets.message_instruction_index);
+    }
+    data.extend_from_slice(&payload);
+
+    let response = Secp256k1InstructionResponse {
+        program_id: solana_sdk::secp256k1_program::id().to_string(),
+        accounts: vec![],
+        instruction_data: base64::engine::general_purpose::STANDARD.encode(&data),
+        offsets,
+    };
+
+    telemetry::record_instruction_built(&response.program_id);
+    Ok(Json(ApiResponse::success(response)))
+}
+
+fn resolve_entries<E, O>(
+    entries: &[E],
+    resolve: impl Fn(&E) -> Result<SignatureSource<O>, AppError>,
+) -> Result<Vec<SignatureSource<O>>, AppError> {
+    if entries.is_empty() {
+        return Err(AppError::invalid(
+            ErrorCode::EmptyBatch,
+            "signatures",
+            "At least one signature is required",
+        ));
+    }
+
+    if entries.len() > u8::MAX as usize {
+        return Err(AppError::invalid(
+            ErrorCode::TooManyItems,
+            "signatures",
+            format!(
+                "Too many signatures: expected at most {}, got {}",
+                u8::MAX,
+                entries.len()
+            ),
+        ));
+    }
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            resolve(entry).map_err(|e| e.in_item("Entry", "signatures", index))
+        })
+        .collect()
+}
+
+/// Lay out instruction data for the Ed25519 program's 14-byte offsets header
+fn build_offsets_instruction_data(
+    sources: Vec<SignatureSource<SignatureOffsets>>,
+) -> Result<(Vec<u8>, Vec<SignatureOffsets>), AppError> {
+    // Header is [num_signatures, padding] followed by one offsets struct per
+    // signature; inline pubkeys, signatures and messages are appended after it.
+    let header_len =
+        SIGNATURE_OFFSETS_START + sources.len() * SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+    let mut payload = Vec::new();
+    let mut offsets = Vec::with_capacity(sources.len());
+
+    for source in sources {
+        let entry_offsets = match source {
+            SignatureSource::Reference(offsets) => offsets,
+            SignatureSource::Inline {
+                pubkey,
+                signature,
+                message,
+            } => {
+                let public_key_offset = data_offset(header_len + payload.len())?;
+                payload.extend_from_slice(&pubkey);
+                let signature_offset = data_offset(header_len + payload.len())?;
+                payload.extend_from_slice(&signature);
+                let message_data_offset = data_offset(header_len + payload.len())?;
+                let message_data_size = data_offset(message.len())?;
+                payload.extend_from_slice(&message);
+                ensure_fits_u16(header_len + payload.len())?;
+
+                SignatureOffsets {
+                    signature_offset,
+                    signature_instruction_index: CURRENT_INSTRUCTION,
+                    public_key_offset,
+                    public_key_instruction_index: CURRENT_INSTRUCTION,
+                    message_data_offset,
+                    message_data_size,
+                    message_instruction_index: CURRENT_INSTRUCTION,
+                }
+            }
+        };
+        offsets.push(entry_offsets);
+    }
+
+    let mut data = Vec::with_capacity(header_len + payload.len());
+    data.extend_from_slice(&[offsets.len() as u8, 0]);
+    for entry_offsets in &offsets {
+        for field in [
+            entry_offsets.signature_offset,
+            entry_offsets.signature_instruction_index,
+            entry_offsets.public_key_offset,
+            entry_offsets.public_key_instruction_index,
+            entry_offsets.message_data_offset,
+            entry_offsets.message_data_size,
+            entry_offsets.message_instruction_index,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+    }
+    data.extend_from_slice(&payload);
+
+    Ok((data, offsets))
+}
+
+fn resolve_ed25519_entry(
+    entry: &Ed25519SignatureEntry,
+) -> Result<SignatureSource<SignatureOffsets>, AppError> {
+    if let Some(offsets) = entry.offsets {
+        reject_inline_fields(&[
+            entry.message.is_some(),
+            entry.signature.is_some(),
+            entry.pubkey.is_some(),
+            entry.secret.is_some(),
+        ])?;
+        return Ok(SignatureSource::Reference(offsets));
+    }
+
+    let message = entry.message.as_deref().unwrap_or_default();
+    validate_not_empty(message, "message")?;
+
+    let (pubkey, signature) = match entry.secret.as_ref().map(Secret::expose) {
+        Some(secret) => {
+            reject_signature_with_secret(&entry.signature)?;
+            let keypair = parse_secret_key(secret)?;
+            if let Some(pubkey) = entry.pubkey.as_deref() {
+                if parse_pubkey(pubkey, "pubkey")? != keypair.pubkey() {
+                    return Err(AppError::invalid(
+                        ErrorCode::ConflictingFields,
+                        "pubkey",
+                        "pubkey does not match secret",
+                    ));
+                }
+            }
+            (keypair.pubkey(), keypair.sign_message(message.as_bytes()))
+        }
+        None => {
+            let signature = entry.signature.as_deref().unwrap_or_default();
+            validate_not_empty(signature, "signature")?;
+            let pubkey = parse_pubkey(entry.pubkey.as_deref().unwrap_or_default(), "pubkey")?;
+            (pubkey, parse_signature(signature)?)
+        }
+    };
+
+    Ok(SignatureSource::Inline {
+        pubkey: pubkey.to_bytes().to_vec(),
+        signature: signature.as_ref().to_vec(),
+        message: message.as_bytes().to_vec(),
+    })
+}
+
+fn resolve_secp256k1_entry(
+    entry: &Secp256k1SignatureEntry,
+) -> Result<SignatureSource<Secp256k1Offsets>, AppError> {
+    if let Some(offsets) = entry.offsets {
+        reject_inline_fields(&[
+            entry.message.is_some(),
+            entry.signature.is_some(),
+            entry.eth_address.is_some(),
+            entry.secret.is_some(),
+        ])?;
+        return Ok(SignatureSource::Reference(offsets));
+    }
+
+    let message = entry.message.as_deref().unwrap_or_default();
+    validate_not_empty(message, "message")?;
+
+    let (eth_address, signature) = match entry.secret.as_ref().map(Secret::expose) {
+        Some(secret) => {
+            reject_signature_with_secret(&entry.signature)?;
+            let secret_key = parse_secp256k1_secret_key(secret)?;
+            let eth_address =
+                construct_eth_pubkey(&libsecp256k1::PublicKey::from_secret_key(&secret_key));
+            if let Some(expected) = entry.eth_address.as_deref() {
+                if parse_eth_address(expected)? != eth_address {
+                    return Err(AppError::invalid(
+                        ErrorCode::ConflictingFields,
+                        "eth_address",
+                        "eth_address does not match secret",
+                    ));
+                }
+            }
+            let message_hash: [u8; 32] = Keccak256::digest(message.as_bytes()).into();
+            let (signature, recovery_id) =
+                libsecp256k1::sign(&libsecp256k1::Message::parse(&message_hash), &secret_key);
+            let mut signature = signature.serialize().to_vec();
+            signature.push(recovery_id.serialize());
+            (eth_address, signature)
+        }
+        None => {
+            let eth_address = parse_eth_address(entry.eth_address.as_deref().unwrap_or_default())?;
+            let mut signature =
+                parse_hex(entry.signature.as_deref().unwrap_or_default(), "signature", 65)?;
+            // Accept both raw (0/1) and Ethereum-style (27/28) recovery ids
+            let recovery_id = libsecp256k1::RecoveryId::parse_rpc(signature[64])
+                .or_else(|_| libsecp256k1::RecoveryId::parse(signature[64]))
+                .map_err(|_| {
+                    AppError::invalid(
+                        ErrorCode::InvalidSignature,
+                        "signature",
+                        "Invalid signature recovery id",
+                    )
+                })?;
+            signature[64] = recovery_id.serialize();
+            (eth_address, signature)
+        }
+    };
+
+    Ok(SignatureSource::Inline {
+        pubkey: eth_address.to_vec(),
+        signature,
+        message: message.as_bytes().to_vec(),
+    })
+}
+
+fn reject_inline_fields(present: &[bool]) -> Result<(), AppError> {
+    if present.contains(&true) {
+        return Err(AppError::invalid(
+            ErrorCode::ConflictingFields,
+            "offsets",
+            "offsets cannot be combined with inline message, signature, key or secret",
+        ));
+    }
+    Ok(())
+}
+
+fn reject_signature_with_secret(signature: &Option<String>) -> Result<(), AppError> {
+    if signature.is_some() {
+        return Err(AppError::invalid(
+            ErrorCode::ConflictingFields,
+            "signature",
+            "signature cannot be combined with secret",
+        ));
+    }
+    Ok(())
+}
+
+/// The instruction data must end within reach of a u16 offset
+fn ensure_fits_u16(len: usize) -> Result<(), AppError> {
+    data_offset(len).map(|_| ())
+}
+
+fn data_offset(offset: usize) -> Result<u16, AppError> {
+    u16::try_from(offset).map_err(|_| {
+        AppError::invalid(
+            ErrorCode::InvalidLength,
+            "signatures",
+            format!("Instruction data exceeds maximum size ({} bytes)", u16::MAX),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{
+        ed25519_instruction::{new_ed25519_instruction, verify},
+        feature_set::FeatureSet,
+        secp256k1_instruction::{self, new_secp256k1_instruction},
+        signature::Keypair,
+    };
+    use super::*;
+
+    fn secret(keypair: &Keypair) -> Secret {
+        let encoded = bs58::encode(keypair.to_bytes()).into_string();
+        serde_json::from_value(encoded.into()).unwrap()
+    }
+
+    fn inline_entry(keypair: &Keypair, message: &str) -> Ed25519SignatureEntry {
+        Ed25519SignatureEntry {
+            message: Some(message.to_string()),
+            signature: None,
+            pubkey: None,
+            secret: Some(secret(keypair)),
+            offsets: None,
+        }
+    }
+
+    async fn build(signatures: Vec<Ed25519SignatureEntry>) -> Vec<u8> {
+        let req = Ed25519InstructionRequest { signatures };
+        let Json(response) = build_ed25519_instruction(ApiJson(req)).await.unwrap();
+        let data = response.data.unwrap().instruction_data;
+        base64::engine::general_purpose::STANDARD.decode(data).unwrap()
+    }
+
+    #[tokio::test]
+    async fn single_signature_matches_the_sdk_layout() {
+        let keypair = Keypair::new();
+        let data = build(vec![inline_entry(&keypair, "hello")]).await;
+
+        let dalek = ed25519_dalek::Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+        assert_eq!(data, new_ed25519_instruction(&dalek, b"hello").data);
+    }
+
+    #[tokio::test]
+    async fn multiple_signatures_pass_the_precompile() {
+        let entries = ["first", "second message", "third"]
+            .iter()
+            .map(|message| inline_entry(&Keypair::new(), message))
+            .collect();
+        let data = build(entries).await;
+
+        assert_eq!(data[0], 3);
+        verify(&data, &[&data], &FeatureSet::all_enabled()).unwrap();
+    }
+
+    fn secp256k1_secret(secret_key: &libsecp256k1::SecretKey) -> Secret {
+        let encoded = hex::encode(secret_key.serialize());
+        serde_json::from_value(encoded.into()).unwrap()
+    }
+
+    fn secp256k1_entry(
+        secret_key: &libsecp256k1::SecretKey,
+        message: &str,
+    ) -> Secp256k1SignatureEntry {
+        Secp256k1SignatureEntry {
+            message: Some(message.to_string()),
+            signature: None,
+            eth_address: None,
+            secret: Some(secp256k1_secret(secret_key)),
+            offsets: None,
+        }
+    }
+
+    async fn build_secp256k1(signatures: Vec<Secp256k1SignatureEntry>) -> Vec<u8> {
+        let req = Secp256k1InstructionRequest {
+            signatures,
+            instruction_index: 0,
+        };
+        let Json(response) = build_secp256k1_instruction(ApiJson(req)).await.unwrap();
+        let data = response.data.unwrap().instruction_data;
+        base64::engine::general_purpose::STANDARD.decode(data).unwrap()
+    }
+
+    fn secp256k1_key(byte: u8) -> libsecp256k1::SecretKey {
+        libsecp256k1::SecretKey::parse(&[byte; 32]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn secp256k1_signature_matches_the_sdk_layout() {
+        let secret_key = secp256k1_key(1);
+        let data = build_secp256k1(vec![secp256k1_entry(&secret_key, "hello")]).await;
+
+        assert_eq!(data, new_secp256k1_instruction(&secret_key, b"hello").data);
+    }
+
+    #[tokio::test]
+    async fn secp256k1_signatures_pass_the_precompile() {
+        let entries = vec![
+            secp256k1_entry(&secp256k1_key(1), "first"),
+            secp256k1_entry(&secp256k1_key(2), "second message"),
+        ];
+        let data = build_secp256k1(entries).await;
+
+        assert_eq!(data[0], 2);
+        secp256k1_instruction::verify(&data, &[&data], &FeatureSet::all_enabled()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn secp256k1_signatures_from_elsewhere_pass_the_precompile() {
+        // Signed outside the API, with an Ethereum-style recovery id
+        let secret_key = secp256k1_key(3);
+        let message_hash: [u8; 32] = Keccak256::digest(b"bridged").into();
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&libsecp256k1::Message::parse(&message_hash), &secret_key);
+        let mut signature = signature.serialize().to_vec();
+        signature.push(recovery_id.serialize() + 27);
+        let eth_address =
+            construct_eth_pubkey(&libsecp256k1::PublicKey::from_secret_key(&secret_key));
+
+        let entry = Secp256k1SignatureEntry {
+            message: Some("bridged".to_string()),
+            signature: Some(hex::encode(signature)),
+            eth_address: Some(hex::encode(eth_address)),
+            secret: None,
+            offsets: None,
+        };
+        let data = build_secp256k1(vec![entry]).await;
+        secp256k1_instruction::verify(&data, &[&data], &FeatureSet::all_enabled()).unwrap();
+
+        // A different message no longer recovers to the address
+        let mut tampered = data.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        let feature_set = FeatureSet::all_enabled();
+        assert!(secp256k1_instruction::verify(&tampered, &[&tampered], &feature_set).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use axum::{
+    body::Bytes,
+    extract::{rejection::BytesRejection, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::{stream, StreamExt};
+use serde_json::{json, Map, Value};
+use crate::{
+    config::MAX_DISPATCH_BATCH_SIZE,
+    dispatch::{self, Caller, Operation, Outcome},
+    error::{AppError, ErrorCode},
+    extract::body_rejection,
+    state::AppState,
+    types::{JsonRpcError, JsonRpcRequest, JsonRpcResponse},
+};
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const INTERNAL_ERROR: i32 = -32603;
+/// Failures other than bad params, e.g. authentication, rate limits or RPC errors
+const SERVER_ERROR: i32 = -32000;
+
+/// Call any operation over JSON-RPC 2.0
+///
+/// Methods are named after their handlers, e.g. `send_sol`, and take the route's request
+/// body as `params`. Each call goes through the same authentication, limits and
+/// validation as its route; failures carry the route's error envelope in `error.data`.
+/// Batches are answered in order. Notifications get no response, so a batch of only
+/// notifications is answered with 204. `send_transaction` is only accepted as a single
+/// call, since a batch cut short by its timeout would lose the submitted signatures.
+#[utoipa::path(
+    post,
+    path = "/rpc",
+    tag = "rpc",
+    request_body = JsonRpcRequest,
+    responses(
+        (status = 200, body = JsonRpcResponse),
+        (status = 204, description = "Only notifications were sent"),
+    ),
+)]
+pub async fn handle_rpc(
+    State(state): State<AppState>,
+    caller: Caller,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Response, AppError> {
+    let body = body.map_err(body_rejection)?;
+    let Ok(payload) = serde_json::from_slice::<Value>(&body) else {
+        return Ok(reply(Some(failure(Value::Null, PARSE_ERROR, "Parse error", None))));
+    };
+
+    let calls = match payload {
+        Value::Array(calls) => calls,
+        call => return Ok(reply(handle_call(&state, &caller, call, false).await)),
+    };
+    if calls.is_empty() {
+        return Ok(reply(Some(failure(Value::Null, INVALID_REQUEST, "Empty batch", None))));
+    }
+    if calls.len() > MAX_DISPATCH_BATCH_SIZE {
+        let message = format!("Batch exceeds {} calls", MAX_DISPATCH_BATCH_SIZE);
+        let data = json!({ "code": ErrorCode::TooManyItems });
+        return Ok(reply(Some(failure(Value::Null, INVALID_REQUEST, &message, Some(data)))));
+    }
+
+    let routes = calls
+        .iter()
+        .filter_map(|call| Operation::find(call.get("method")?.as_str()?))
+        .map(|operation| operation.route);
+    let concurrency = dispatch::concurrency(&state.config, routes);
+    let responses = stream::iter(calls)
+        .map(|call| handle_call(&state, &caller, call, true))
+        .buffered(concurrency)
+        .filter_map(|response| async move { response })
+        .collect::<Vec<_>>()
+        .await;
+
+    Ok(if responses.is_empty() {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        Json(responses).into_response()
+    })
+}
+
+fn reply(response: Option<JsonRpcResponse>) -> Response {
+    match response {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    }
+}
+
+/// Run one call, `batched` when it came in an array; `None` for notifications
+async fn handle_call(
+    state: &AppState,
+    caller: &Caller,
+    call: Value,
+    batched: bool,
+) -> Option<JsonRpcResponse> {
+    let id = call
+        .get("id")
+        .filter(|id| is_valid_id(id))
+        .cloned()
+        .unwrap_or(Value::Null);
+    let request = match serde_json::from_value::<JsonRpcRequest>(call) {
+        Ok(request)
+            if request.jsonrpc == "2.0" && request.id.as_ref().is_none_or(is_valid_id) =>
+        {
+            request
+        }
+        _ => return Some(failure(id, INVALID_REQUEST, "Invalid Request", None)),
+    };
+
+    let Some(operation) =
+        Operation::find(&request.method).filter(|operation| operation.enabled(&state.config))
+    else {
+        let data = json!({ "method": request.method });
+        return Some(failure(id, METHOD_NOT_FOUND, "Method not found", Some(data)));
+    };
+    if batched && operation.route == "/transaction/send" {
+        let message = format!("{} cannot be batched; send it as a single call", operation.name);
+        let data = json!({ "code": ErrorCode::InvalidValue, "method": request.method });
+        return Some(failure(id, INVALID_REQUEST, &message, Some(data)));
+    }
+
+    let outcome = state.dispatcher.call(operation, request.params, caller).await;
+    let id = request.id?;
+
+    Some(if outcome.is_success() {
+        let mut body = outcome.body;
+        JsonRpcResponse::success(id, body["data"].take())
+    } else {
+        JsonRpcResponse::error(id, call_error(outcome))
+    })
+}
+
+fn is_valid_id(id: &Value) -> bool {
+    matches!(id, Value::String(_) | Value::Number(_) | Value::Null)
+}
+
+fn failure(id: Value, code: i32, message: &str, data: Option<Value>) -> JsonRpcResponse {
+    JsonRpcResponse::error(
+        id,
+        JsonRpcError {
+            code,
+            message: message.to_string(),
+            data,
+        },
+    )
+}
+
+/// Error object for a failed call, carrying its envelope and HTTP status as `data`
+fn call_error(outcome: Outcome) -> JsonRpcError {
+    let mut data = match outcome.body {
+        Value::Object(envelope) => envelope,
+        _ => Map::new(),
+    };
+    data.remove("success");
+    let message = match data.remove("error") {
+        Some(Value::String(message)) => message,
+        _ => outcome.status.to_string(),
+    };
+    data.insert("status".to_string(), outcome.status.as_u16().into());
+
+    let code = match outcome.status {
+        StatusCode::BAD_REQUEST
+        | StatusCode::PAYLOAD_TOO_LARGE
+        | StatusCode::UNSUPPORTED_MEDIA_TYPE
+        | StatusCode::UNPROCESSABLE_ENTITY => INVALID_PARAMS,
+        StatusCode::INTERNAL_SERVER_ERROR => INTERNAL_ERROR,
+        _ => SERVER_ERROR,
+    };
+
+    JsonRpcError {
+        code,
+        message,
+        data: Some(Value::Object(data)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::StatusCode};
+    use serde_json::{json, Value};
+    use crate::{config::Config, testing};
+    use super::*;
+
+    async fn rpc(body: Value) -> testing::TestResponse {
+        let app = testing::app(Config::default());
+        testing::call(&app, testing::post("/rpc", body)).await
+    }
+
+    fn rent_call(id: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": "calc_rent", "params": { "data_len": 0 } })
+    }
+
+    #[tokio::test]
+    async fn unparseable_bodies_are_parse_errors() {
+        let app = testing::app(Config::default());
+        let mut req = testing::post("/rpc", Value::Null);
+        *req.body_mut() = Body::from(r#"{"jsonrpc": "2.0", "method""#);
+
+        let response = testing::call(&app, req).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["error"]["code"], PARSE_ERROR);
+        assert!(response.body["id"].is_null());
+    }
+
+    #[tokio::test]
+    async fn malformed_calls_are_invalid_requests() {
+        let wrong_version = json!({ "jsonrpc": "1.0", "id": 1, "method": "calc_rent" });
+        let object_id = json!({ "jsonrpc": "2.0", "id": {}, "method": "calc_rent" });
+        for call in [wrong_version, object_id, json!(7), json!([])] {
+            let response = rpc(call.clone()).await;
+            assert_eq!(response.status, StatusCode::OK);
+            assert_eq!(response.body["error"]["code"], INVALID_REQUEST, "{}", call);
+        }
+
+        let response = rpc(json!({ "jsonrpc": "1.0", "id": 1, "method": "calc_rent" })).await;
+        assert_eq!(response.body["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn unknown_methods_are_not_found() {
+        let response = rpc(json!({ "jsonrpc": "2.0", "id": 1, "method": "nope" })).await;
+        assert_eq!(response.body["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(response.body["error"]["data"]["method"], "nope");
+        assert_eq!(response.body["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn notifications_get_no_response() {
+        let mut notification = rent_call(Value::Null);
+        notification.as_object_mut().unwrap().remove("id");
+
+        for body in [notification.clone(), json!([notification.clone(), notification])] {
+            let response = rpc(body).await;
+            assert_eq!(response.status, StatusCode::NO_CONTENT);
+            assert!(response.body.is_null());
+        }
+    }
+
+    #[tokio::test]
+    async fn null_ids_are_still_answered() {
+        let response = rpc(rent_call(Value::Null)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body["id"].is_null());
+        assert!(response.body["result"]["lamports"].is_u64(), "{}", response.body);
+    }
+
+    #[tokio::test]
+    async fn batches_are_answered_in_order_without_notifications() {
+        let notification = json!({ "jsonrpc": "2.0", "method": "calc_rent", "params": {} });
+        let unknown = json!({ "jsonrpc": "2.0", "id": "b", "method": "nope" });
+        let calls = json!([rent_call(json!("a")), notification, unknown, rent_call(json!(3))]);
+
+        let response = rpc(calls).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let responses = response.body.as_array().unwrap();
+        let ids: Vec<_> = responses.iter().map(|response| response["id"].clone()).collect();
+        assert_eq!(ids, [json!("a"), json!("b"), json!(3)]);
+        assert!(responses[0]["result"].is_object());
+        assert_eq!(responses[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert!(responses[2]["result"].is_object());
+    }
+
+    #[tokio::test]
+    async fn validation_errors_are_invalid_params() {
+        let params = json!({ "data_len": u64::MAX });
+        let call = json!({ "jsonrpc": "2.0", "id": 1, "method": "calc_rent", "params": params });
+
+        let response = rpc(call).await;
+        let error = &response.body["error"];
+        assert_eq!(error["code"], INVALID_PARAMS, "{}", response.body);
+        assert_eq!(error["data"]["status"], 400);
+        assert_eq!(error["data"]["code"], "INVALID_LENGTH");
+        assert_eq!(error["data"]["field"], "data_len");
+    }
+}
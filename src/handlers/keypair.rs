@@ -1,14 +1,57 @@
 use axum::Json;
-use rand::rngs::OsRng;
-use solana_sdk::signature::{Keypair, Signer};
-use crate::{error::AppError, types::{ApiResponse, KeypairResponse}};
+use rand::{rngs::OsRng, RngCore};
+use solana_sdk::{
+    secp256k1_instruction::construct_eth_pubkey,
+    signature::{Keypair, Signer},
+};
+use crate::{
+    error::AppError,
+    telemetry,
+    types::{ApiResponse, KeypairResponse, Secp256k1KeypairResponse},
+    utils::encode_eth_address,
+};
 
 /// Generate a new Solana keypair
+#[utoipa::path(
+    post,
+    path = "/keypair",
+    tag = "keypair",
+    responses((status = 200, body = ApiResponse<KeypairResponse>)),
+)]
 pub async fn generate_keypair() -> Result<Json<ApiResponse<KeypairResponse>>, AppError> {
     let keypair = Keypair::new();
     let pubkey = keypair.pubkey().to_string();
     let secret = bs58::encode(keypair.to_bytes()).into_string();
 
     let response = KeypairResponse { pubkey, secret };
+    telemetry::record_keypair_generated("ed25519");
     Ok(Json(ApiResponse::success(response)))
-}
\ No newline at end of file
+}
+
+/// Generate a new secp256k1 keypair with its Ethereum address
+#[utoipa::path(
+    post,
+    path = "/keypair/secp256k1",
+    tag = "keypair",
+    responses((status = 200, body = ApiResponse<Secp256k1KeypairResponse>)),
+)]
+pub async fn generate_secp256k1_keypair(
+) -> Result<Json<ApiResponse<Secp256k1KeypairResponse>>, AppError> {
+    // Retry until the random bytes fall inside the curve order
+    let secret_key = loop {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        if let Ok(secret_key) = libsecp256k1::SecretKey::parse(&bytes) {
+            break secret_key;
+        }
+    };
+    let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+
+    let response = Secp256k1KeypairResponse {
+        secret: format!("0x{}", hex::encode(secret_key.serialize())),
+        public_key: format!("0x{}", hex::encode(public_key.serialize())),
+        eth_address: encode_eth_address(&construct_eth_pubkey(&public_key)),
+    };
+    telemetry::record_keypair_generated("secp256k1");
+    Ok(Json(ApiResponse::success(response)))
+}
@@ -1,24 +1,38 @@
-use axum::{extract::Json as AxumJson, Json};
+use axum::Json;
 use base64::Engine;
-use solana_sdk::signature::Signer;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use solana_sdk::{pubkey::Pubkey, signature::{Signature, Signer}};
 use crate::{
-    error::AppError,
+    config::MAX_VERIFY_BATCH_SIZE,
+    error::{AppError, ErrorCode, Validator},
+    extract::ApiJson,
+    telemetry,
     types::{
-        ApiResponse, MessageSignRequest, MessageSignResponse, MessageVerifyRequest,
-        MessageVerifyResponse,
+        ApiResponse, MessageSignRequest, MessageSignResponse, MessageVerifyBatchResponse,
+        MessageVerifyRequest, MessageVerifyResponse,
     },
-    utils::{parse_pubkey, parse_secret_key, validate_not_empty},
+    utils::{parse_pubkey, parse_secret_key, parse_signature, validate_not_empty},
 };
 
 /// Sign a message with a private key
+#[utoipa::path(
+    post,
+    path = "/message/sign",
+    tag = "message",
+    request_body = MessageSignRequest,
+    responses((status = 200, body = ApiResponse<MessageSignResponse>)),
+)]
 pub async fn sign_message(
-    AxumJson(req): AxumJson<MessageSignRequest>,
+    ApiJson(req): ApiJson<MessageSignRequest>,
 ) -> Result<Json<ApiResponse<MessageSignResponse>>, AppError> {
     // Validate inputs
-    validate_not_empty(&req.message, "message")?;
-    validate_not_empty(&req.secret, "secret")?;
+    let mut validator = Validator::default();
+    let message = validator.check(validate_not_empty(&req.message, "message"));
+    let keypair = validator.check(parse_secret_key(req.secret.expose()));
+    let (Some(()), Some(keypair)) = (message, keypair) else {
+        return Err(validator.into_error());
+    };
 
-    let keypair = parse_secret_key(&req.secret)?;
     let signature = keypair.sign_message(req.message.as_bytes());
     let signature_b64 = base64::engine::general_purpose::STANDARD.encode(signature.as_ref());
     let public_key = keypair.pubkey().to_string();
@@ -29,41 +43,279 @@ pub async fn sign_message(
         message: req.message,
     };
 
+    telemetry::record_message_signed();
     Ok(Json(ApiResponse::success(response)))
 }
 
 /// Verify a message signature
+#[utoipa::path(
+    post,
+    path = "/message/verify",
+    tag = "message",
+    request_body = MessageVerifyRequest,
+    responses((status = 200, body = ApiResponse<MessageVerifyResponse>)),
+)]
 pub async fn verify_message(
-    AxumJson(req): AxumJson<MessageVerifyRequest>,
+    ApiJson(req): ApiJson<MessageVerifyRequest>,
 ) -> Result<Json<ApiResponse<MessageVerifyResponse>>, AppError> {
-    // Validate inputs
-    validate_not_empty(&req.message, "message")?;
-    validate_not_empty(&req.signature, "signature")?;
-    validate_not_empty(&req.pubkey, "pubkey")?;
+    let (pubkey, signature) = parse_verify_request(&req)?;
 
-    let pubkey = parse_pubkey(&req.pubkey, "pubkey")?;
+    let valid = signature.verify(pubkey.as_ref(), req.message.as_bytes());
 
-    let signature_bytes = base64::engine::general_purpose::STANDARD
-        .decode(&req.signature)
-        .map_err(|_| AppError::BadRequest("Invalid base64 for signature".to_string()))?;
+    telemetry::record_message_verified(valid);
 
-    if signature_bytes.len() != 64 {
-        return Err(AppError::BadRequest(format!(
-            "Invalid signature length: expected 64 bytes, got {}",
-            signature_bytes.len()
-        )));
+    let response = MessageVerifyResponse {
+        valid,
+        message: req.message,
+        pubkey: req.pubkey,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Verify many message signatures at once
+///
+/// Entries are checked with a single ed25519 batch verification. If the batch
+/// fails, every entry is re-checked individually to find the invalid ones. Entries
+/// whose key or signature is not a prime-order point are always checked individually,
+/// since the batch accepts some of them that `/message/verify` rejects.
+#[utoipa::path(
+    post,
+    path = "/message/verify/batch",
+    tag = "message",
+    request_body = Vec<MessageVerifyRequest>,
+    responses((status = 200, body = ApiResponse<MessageVerifyBatchResponse>)),
+)]
+pub async fn verify_message_batch(
+    ApiJson(reqs): ApiJson<Vec<MessageVerifyRequest>>,
+) -> Result<Json<ApiResponse<MessageVerifyBatchResponse>>, AppError> {
+    if reqs.is_empty() {
+        return Err(AppError::request(
+            ErrorCode::EmptyBatch,
+            "Batch must contain at least one entry",
+        ));
     }
 
-    let signature = solana_sdk::signature::Signature::try_from(signature_bytes.as_slice())
-        .map_err(|_| AppError::BadRequest("Invalid signature bytes".to_string()))?;
+    if reqs.len() > MAX_VERIFY_BATCH_SIZE {
+        return Err(AppError::request(
+            ErrorCode::TooManyItems,
+            format!("Batch exceeds maximum size ({}).", MAX_VERIFY_BATCH_SIZE),
+        ));
+    }
 
-    let valid = signature.verify(pubkey.as_ref(), req.message.as_bytes());
+    let mut entries = Vec::with_capacity(reqs.len());
+    for (index, req) in reqs.iter().enumerate() {
+        let (pubkey, signature) =
+            parse_verify_request(req).map_err(|e| e.in_item("Entry", "", index))?;
+        entries.push((req.message.as_bytes().to_vec(), pubkey, signature));
+    }
 
-    let response = MessageVerifyResponse {
+    let valid = tokio::task::spawn_blocking(move || verify_batch(&entries))
+        .await
+        .map_err(|_| AppError::Internal)?;
+
+    for &entry_valid in &valid {
+        telemetry::record_message_verified(entry_valid);
+    }
+
+    let response = MessageVerifyBatchResponse {
+        all_valid: valid.iter().all(|v| *v),
         valid,
-        message: req.message,
-        pubkey: req.pubkey,
     };
 
     Ok(Json(ApiResponse::success(response)))
-}
\ No newline at end of file
+}
+
+fn parse_verify_request(req: &MessageVerifyRequest) -> Result<(Pubkey, Signature), AppError> {
+    // Validate inputs, reporting every failing field at once
+    let mut validator = Validator::default();
+    let message = validator.check(validate_not_empty(&req.message, "message"));
+    let pubkey = validator.check(parse_pubkey(&req.pubkey, "pubkey"));
+    let signature = validator.check(
+        validate_not_empty(&req.signature, "signature")
+            .and_then(|_| parse_signature(&req.signature)),
+    );
+
+    match (message, pubkey, signature) {
+        (Some(()), Some(pubkey), Some(signature)) => Ok((pubkey, signature)),
+        _ => Err(validator.into_error()),
+    }
+}
+
+fn verify_batch(entries: &[(Vec<u8>, Pubkey, Signature)]) -> Vec<bool> {
+    // Pubkeys that are not valid curve points can never verify, so they are
+    // marked invalid up front and left out of the batch.
+    let mut valid = vec![false; entries.len()];
+    let mut indices = Vec::with_capacity(entries.len());
+    let mut messages = Vec::with_capacity(entries.len());
+    let mut signatures = Vec::with_capacity(entries.len());
+    let mut public_keys = Vec::with_capacity(entries.len());
+
+    for (index, (message, pubkey, signature)) in entries.iter().enumerate() {
+        let public_key = ed25519_dalek::PublicKey::from_bytes(pubkey.as_ref());
+        let dalek_signature = ed25519_dalek::Signature::try_from(signature.as_ref());
+        let (Ok(public_key), Ok(dalek_signature)) = (public_key, dalek_signature) else {
+            continue;
+        };
+        // `verify_strict` rejects what the cofactored batch equation lets through for
+        // small-order or mixed-order points, so those are only checked on their own
+        if !is_prime_order(pubkey.as_ref()) || !is_prime_order(&signature.as_ref()[..32]) {
+            valid[index] = signature.verify(pubkey.as_ref(), message);
+            continue;
+        }
+        indices.push(index);
+        messages.push(message.as_slice());
+        signatures.push(dalek_signature);
+        public_keys.push(public_key);
+    }
+
+    if ed25519_dalek::verify_batch(&messages, &signatures, &public_keys).is_ok() {
+        for index in indices {
+            valid[index] = true;
+        }
+    } else {
+        for index in indices {
+            let (message, pubkey, signature) = &entries[index];
+            valid[index] = signature.verify(pubkey.as_ref(), message);
+        }
+    }
+
+    valid
+}
+
+/// Whether `bytes` encode a point in the prime-order subgroup other than the identity
+fn is_prime_order(bytes: &[u8]) -> bool {
+    CompressedEdwardsY::from_slice(bytes)
+        .decompress()
+        .is_some_and(|point| !point.is_small_order() && point.is_torsion_free())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::Keypair;
+    use super::*;
+
+    fn entry(keypair: &Keypair, message: &str, signed: &str) -> MessageVerifyRequest {
+        let signature = keypair.sign_message(signed.as_bytes());
+        MessageVerifyRequest {
+            message: message.to_string(),
+            signature: base64::engine::general_purpose::STANDARD.encode(signature.as_ref()),
+            pubkey: keypair.pubkey().to_string(),
+        }
+    }
+
+    /// Entries 1 and 4 carry a signature over a different message
+    fn mixed_entries() -> Vec<MessageVerifyRequest> {
+        (0..6)
+            .map(|index| {
+                let keypair = Keypair::new();
+                let message = format!("message {}", index);
+                let signed = if index % 3 == 1 { "tampered" } else { message.as_str() };
+                entry(&keypair, &message, signed)
+            })
+            .collect()
+    }
+
+    async fn verify_each(entries: &[MessageVerifyRequest]) -> Vec<bool> {
+        let mut valid = Vec::new();
+        for req in entries {
+            let req = MessageVerifyRequest {
+                message: req.message.clone(),
+                signature: req.signature.clone(),
+                pubkey: req.pubkey.clone(),
+            };
+            let Json(response) = verify_message(ApiJson(req)).await.unwrap();
+            valid.push(response.data.unwrap().valid);
+        }
+        valid
+    }
+
+    #[tokio::test]
+    async fn batch_agrees_with_single_verification() {
+        let entries = mixed_entries();
+        let expected = verify_each(&entries).await;
+        assert_eq!(expected, [true, false, true, true, false, true]);
+
+        let Json(response) = verify_message_batch(ApiJson(entries)).await.unwrap();
+        let response = response.data.unwrap();
+        assert_eq!(response.valid, expected);
+        assert!(!response.all_valid);
+    }
+
+    #[tokio::test]
+    async fn batch_of_valid_signatures_passes_without_fallback() {
+        let entries = (0..4)
+            .map(|index| {
+                let message = format!("message {}", index);
+                entry(&Keypair::new(), &message, &message)
+            })
+            .collect::<Vec<_>>();
+
+        let Json(response) = verify_message_batch(ApiJson(entries)).await.unwrap();
+        let response = response.data.unwrap();
+        assert_eq!(response.valid, [true; 4]);
+        assert!(response.all_valid);
+    }
+
+    #[tokio::test]
+    async fn batch_rejects_small_order_keys_like_single_verification() {
+        use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar};
+
+        // With the identity as public key, any `s` and `R = s * B` satisfy the batch
+        // equation for every message
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        let s = Scalar::from(5u64);
+        let r = (&s * &ED25519_BASEPOINT_TABLE).compress();
+        let mut signature = r.to_bytes().to_vec();
+        signature.extend_from_slice(s.as_bytes());
+
+        let mut entries = (0..4)
+            .map(|index| {
+                let message = format!("message {}", index);
+                entry(&Keypair::new(), &message, &message)
+            })
+            .collect::<Vec<_>>();
+        entries.push(MessageVerifyRequest {
+            message: "anything".to_string(),
+            signature: base64::engine::general_purpose::STANDARD.encode(signature),
+            pubkey: Pubkey::new_from_array(identity).to_string(),
+        });
+        let expected = verify_each(&entries).await;
+        assert_eq!(expected, [true, true, true, true, false]);
+
+        let Json(response) = verify_message_batch(ApiJson(entries)).await.unwrap();
+        assert_eq!(response.data.unwrap().valid, expected);
+    }
+
+    #[tokio::test]
+    async fn rejects_empty_and_oversized_batches() {
+        let err = verify_message_batch(ApiJson(Vec::new())).await.err().unwrap();
+        assert_eq!(err.code(), ErrorCode::EmptyBatch);
+
+        let keypair = Keypair::new();
+        let entries = (0..=MAX_VERIFY_BATCH_SIZE)
+            .map(|_| MessageVerifyRequest {
+                message: "m".to_string(),
+                signature: String::new(),
+                pubkey: keypair.pubkey().to_string(),
+            })
+            .collect();
+        let err = verify_message_batch(ApiJson(entries)).await.err().unwrap();
+        assert_eq!(err.code(), ErrorCode::TooManyItems);
+    }
+
+    #[tokio::test]
+    async fn reports_the_malformed_entry() {
+        let mut entries = mixed_entries();
+        entries[2].pubkey = "not-a-pubkey".to_string();
+
+        let err = verify_message_batch(ApiJson(entries)).await.err().unwrap();
+        let AppError::Invalid(error) = err else {
+            panic!("expected a single field error, got {:?}", err);
+        };
+        assert_eq!(error.code, ErrorCode::InvalidBase58);
+        assert_eq!(error.field.as_deref(), Some("[2].pubkey"));
+        assert!(error.message.starts_with("Entry 2: "), "{}", error.message);
+    }
+}
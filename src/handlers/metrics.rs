@@ -0,0 +1,77 @@
+use axum::{extract::State, http::header::CONTENT_TYPE, response::IntoResponse};
+use crate::{error::AppError, state::AppState};
+
+/// Prometheus metrics in the text exposition format
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses((status = 200, content_type = "text/plain; version=0.0.4", body = String)),
+)]
+pub async fn render_metrics(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let handle = state
+        .metrics
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("Metrics are disabled".to_string()))?;
+
+    Ok(([(CONTENT_TYPE, "text/plain; version=0.0.4")], handle.render()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+    use axum::{body::to_bytes, http::StatusCode, Router};
+    use metrics_exporter_prometheus::PrometheusHandle;
+    use serde_json::json;
+    use tower::Service;
+    use crate::{config::Config, routes, telemetry, testing};
+    use super::*;
+
+    /// The recorder is global, so every test shares one
+    fn handle() -> PrometheusHandle {
+        static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+        HANDLE.get_or_init(telemetry::init_metrics).clone()
+    }
+
+    fn app(config: Config) -> Router {
+        routes::app(&AppState::new(config, Some(handle())))
+    }
+
+    async fn scrape(app: &Router) -> (StatusCode, String) {
+        let response = app.clone().call(testing::get("/metrics")).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn requests_are_counted_and_timed() {
+        let app = app(Config::default());
+        let rent = testing::post("/calc/rent", json!({ "data_len": 0 }));
+        assert_eq!(testing::call(&app, rent).await.status, StatusCode::OK);
+        let invalid = testing::post("/calc/rent", json!({ "data_len": u64::MAX }));
+        assert_eq!(testing::call(&app, invalid).await.status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = scrape(&app).await;
+        assert_eq!(status, StatusCode::OK);
+        let labels = r#"method="POST",route="/calc/rent""#;
+        assert!(body.contains(&format!("http_requests_total{{{},status=\"200\"}}", labels)));
+        assert!(body.contains("http_request_errors_total{"), "{}", body);
+        assert!(body.contains("code=\"INVALID_LENGTH\""), "{}", body);
+        assert!(body.contains("# TYPE http_request_duration_seconds histogram"), "{}", body);
+        assert!(body.contains(&format!("http_request_duration_seconds_bucket{{{}", labels)));
+    }
+
+    #[tokio::test]
+    async fn an_admin_port_takes_metrics_off_the_api() {
+        let mut config = Config::default();
+        config.metrics.port = Some(9100);
+        let (status, _) = scrape(&app(config)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // As served on the admin port
+        let state = AppState::new(Config::default(), Some(handle()));
+        let (status, _) = scrape(&routes::metrics_routes().with_state(state)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
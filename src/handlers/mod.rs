@@ -2,4 +2,16 @@ pub mod health;
 pub mod keypair;
 pub mod token;
 pub mod message;
-pub mod send;
\ No newline at end of file
+pub mod send;
+pub mod instruction;
+pub mod ethereum;
+pub mod cluster;
+pub mod account_info;
+pub mod transaction;
+pub mod simulate;
+pub mod decode;
+pub mod calc;
+pub mod jsonrpc;
+pub mod batch;
+pub mod fallback;
+pub mod metrics;
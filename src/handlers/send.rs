@@ -1,28 +1,63 @@
-use axum::{extract::Json as AxumJson, Json};
+use axum::{extract::State, Json};
 use base64::Engine;
 use spl_token::instruction as token_instruction;
-use solana_program::pubkey::Pubkey;
 use crate::{
-    error::AppError,
+    config::SOL_DECIMALS,
+    error::{AppError, ErrorCode, Validator},
+    extract::ApiJson,
+    state::AppState,
+    telemetry,
     types::{
         ApiResponse, SendSolRequest, SendSolResponse, SendTokenRequest, SendTokenResponse,
         SendTokenAccountMeta,
     },
-    utils::{parse_pubkey, validate_amount, validate_different_pubkeys},
+    utils::{
+        parse_amount, parse_pubkey, parse_ui_amount, resolve_token_amount,
+        validate_different_pubkeys,
+    },
 };
 
 /// Create a SOL transfer instruction
+#[utoipa::path(
+    post,
+    path = "/send/sol",
+    tag = "send",
+    request_body = SendSolRequest,
+    responses((status = 200, body = ApiResponse<SendSolResponse>)),
+)]
 pub async fn send_sol(
-    AxumJson(req): AxumJson<SendSolRequest>,
+    ApiJson(req): ApiJson<SendSolRequest>,
 ) -> Result<Json<ApiResponse<SendSolResponse>>, AppError> {
-    let from = parse_pubkey(&req.from, "from")?;
-    let to = parse_pubkey(&req.to, "to")?;
+    // Validate inputs, reporting every failing field at once
+    let mut validator = Validator::default();
+    let from = validator.check(parse_pubkey(&req.from, "from"));
+    let to = validator.check(parse_pubkey(&req.to, "to"));
+
+    if let (Some(from), Some(to)) = (from, to) {
+        validator.check(validate_different_pubkeys(&from, &to, "From", "to"));
+    }
 
-    // Validate inputs
-    validate_different_pubkeys(&from, &to, "From", "to")?;
-    validate_amount(req.lamports, "Lamports")?;
+    let lamports = validator.check(match (&req.lamports, &req.sol) {
+        (Some(lamports), None) => parse_amount(lamports, "Lamports"),
+        (None, Some(sol)) => parse_ui_amount(sol, SOL_DECIMALS, "sol"),
+        (Some(_), Some(_)) => Err(AppError::invalid(
+            ErrorCode::ConflictingFields,
+            "sol",
+            "Provide either lamports or sol, not both",
+        )),
+        (None, None) => Err(AppError::invalid(
+            ErrorCode::MissingField,
+            "lamports",
+            "Missing required field: lamports or sol",
+        )),
+    });
 
-    let ix = solana_sdk::system_instruction::transfer(&from, &to, req.lamports);
+    let (Some(from), Some(to), Some(lamports)) = (from, to, lamports) else {
+        return Err(validator.into_error());
+    };
+    validator.finish()?;
+
+    let ix = solana_sdk::system_instruction::transfer(&from, &to, lamports);
 
     let accounts = ix
         .accounts
@@ -38,39 +73,72 @@ pub async fn send_sol(
         instruction_data,
     };
 
+    telemetry::record_instruction_built(&response.program_id);
     Ok(Json(ApiResponse::success(response)))
 }
 
 /// Create a token transfer instruction
+#[utoipa::path(
+    post,
+    path = "/send/token",
+    tag = "send",
+    request_body = SendTokenRequest,
+    responses((status = 200, body = ApiResponse<SendTokenResponse>)),
+)]
 pub async fn send_token(
-    AxumJson(req): AxumJson<SendTokenRequest>,
+    State(state): State<AppState>,
+    ApiJson(req): ApiJson<SendTokenRequest>,
 ) -> Result<Json<ApiResponse<SendTokenResponse>>, AppError> {
-    // Parse using solana_program::pubkey::Pubkey first
-    let destination = req.destination.parse::<Pubkey>()
-        .map_err(|_| AppError::BadRequest("Invalid destination pubkey".to_string()))?;
-    let mint = req.mint.parse::<Pubkey>()
-        .map_err(|_| AppError::BadRequest("Invalid mint pubkey".to_string()))?;
-    let owner = req.owner.parse::<Pubkey>()
-        .map_err(|_| AppError::BadRequest("Invalid owner pubkey".to_string()))?;
-
-    // Convert to solana_sdk::pubkey::Pubkey for the instruction
-    let destination_sdk = solana_sdk::pubkey::Pubkey::from(destination.to_bytes());
-    let mint_sdk = solana_sdk::pubkey::Pubkey::from(mint.to_bytes());
-    let owner_sdk = solana_sdk::pubkey::Pubkey::from(owner.to_bytes());
-
-    // Validate inputs using SDK pubkeys
-    validate_different_pubkeys(&destination_sdk, &owner_sdk, "Destination", "owner")?;
-    validate_amount(req.amount, "Amount")?;
+    // Validate inputs, reporting every failing field at once
+    let mut validator = Validator::default();
+    let destination = validator.check(parse_pubkey(&req.destination, "destination"));
+    let mint = validator.check(parse_pubkey(&req.mint, "mint"));
+    let owner = validator.check(parse_pubkey(&req.owner, "owner"));
+
+    if let (Some(destination), Some(owner)) = (destination, owner) {
+        validator.check(validate_different_pubkeys(
+            &destination,
+            &owner,
+            "Destination",
+            "owner",
+        ));
+    }
+
+    let amount = match mint {
+        Some(mint) => validator.check(
+            resolve_token_amount(
+                &state,
+                &mint,
+                req.amount.as_ref(),
+                req.ui_amount.as_deref(),
+                req.decimals,
+                req.cluster.as_deref(),
+            )
+            .await,
+        ),
+        None => None,
+    };
+
+    let (Some(destination), Some(mint), Some(owner), Some(amount)) =
+        (destination, mint, owner, amount)
+    else {
+        return Err(validator.into_error());
+    };
+    validator.finish()?;
 
     let ix = token_instruction::transfer(
         &spl_token::id(),
-        &mint_sdk,
-        &destination_sdk,
-        &owner_sdk,
+        &mint,
+        &destination,
+        &owner,
         &[],
-        req.amount,
+        amount,
     )
-    .map_err(|e| AppError::BadRequest(format!("Failed to create instruction: {}", e)))?;
+    // Only fails for a foreign token program id, which is never passed here
+    .map_err(|err| {
+        tracing::error!(error = %err, "failed to create token instruction");
+        AppError::Internal
+    })?;
 
     let accounts = ix
         .accounts
@@ -89,5 +157,6 @@ pub async fn send_token(
         instruction_data,
     };
 
+    telemetry::record_instruction_built(&response.program_id);
     Ok(Json(ApiResponse::success(response)))
 }
\ No newline at end of file
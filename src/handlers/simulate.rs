@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+use axum::Json;
+use base64::Engine;
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+use crate::{
+    config::{MAX_SIMULATION_ACCOUNTS, MAX_SIMULATION_DATA_BYTES},
+    decode::{decode_account_data, decode_custom_error},
+    error::{AppError, ErrorCode},
+    extract::ApiJson,
+    types::{
+        ApiResponse, DecodedInstructionError, LocalAccountSeed, LocalAccountState,
+        LocalSimulationRequest, LocalSimulationResponse,
+    },
+    utils::{decode_base64, parse_instruction, parse_pubkey},
+};
+
+/// Execute instructions against an in-process bank seeded with the given accounts
+///
+/// At most `MAX_SIMULATION_ACCOUNTS` accounts holding `MAX_SIMULATION_DATA_BYTES` of data
+/// in total can be seeded.
+#[utoipa::path(
+    post,
+    path = "/simulate/local",
+    tag = "simulate",
+    request_body = LocalSimulationRequest,
+    responses((status = 200, body = ApiResponse<LocalSimulationResponse>)),
+)]
+pub async fn simulate_local(
+    ApiJson(req): ApiJson<LocalSimulationRequest>,
+) -> Result<Json<ApiResponse<LocalSimulationResponse>>, AppError> {
+    if req.instructions.is_empty() {
+        return Err(AppError::invalid(
+            ErrorCode::MissingField,
+            "instructions",
+            "At least one instruction is required",
+        ));
+    }
+
+    let signers = req
+        .signers
+        .iter()
+        .map(|signer| parse_pubkey(signer, "signers"))
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    let instructions = req
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| {
+            parse_instruction(instruction, &signers)
+                .map_err(|e| e.in_item("Instruction", "instructions", index))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if req.accounts.len() > MAX_SIMULATION_ACCOUNTS {
+        return Err(AppError::invalid(
+            ErrorCode::TooManyItems,
+            "accounts",
+            format!(
+                "Too many accounts: expected at most {}, got {}",
+                MAX_SIMULATION_ACCOUNTS,
+                req.accounts.len()
+            ),
+        ));
+    }
+
+    let fee_payer = req
+        .fee_payer
+        .as_deref()
+        .map(|fee_payer| parse_pubkey(fee_payer, "fee_payer"))
+        .transpose()?;
+
+    let seeds = req
+        .accounts
+        .iter()
+        .enumerate()
+        .map(|(index, seed)| {
+            parse_account_seed(seed).map_err(|e| e.in_item("Account", "accounts", index))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let data_bytes: usize = seeds.iter().map(|(_, account)| account.data.len()).sum();
+    if data_bytes > MAX_SIMULATION_DATA_BYTES {
+        return Err(AppError::invalid(
+            ErrorCode::InvalidValue,
+            "accounts",
+            format!(
+                "Account data too large: expected at most {} bytes in total, got {}",
+                MAX_SIMULATION_DATA_BYTES, data_bytes
+            ),
+        ));
+    }
+
+    let mut program_test = ProgramTest::default();
+    for (pubkey, account) in seeds {
+        program_test.add_account(pubkey, account);
+    }
+
+    let mut context = program_test.start_with_context().await;
+    let payer = fee_payer.unwrap_or_else(|| context.payer.pubkey());
+
+    // Report every account the instructions touch, plus a caller-provided fee payer
+    let mut touched = Vec::new();
+    for pubkey in fee_payer.iter().chain(
+        instructions
+            .iter()
+            .flat_map(|instruction| instruction.accounts.iter().map(|meta| &meta.pubkey)),
+    ) {
+        if !touched.contains(pubkey) {
+            touched.push(*pubkey);
+        }
+    }
+
+    // The bank executes without signature verification, so signer accounts
+    // only need to be flagged in the message, not actually signed for.
+    let message = Message::new_with_blockhash(&instructions, Some(&payer), &context.last_blockhash);
+    let transaction = Transaction::new_unsigned(message);
+
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .map_err(|_| AppError::Internal)?;
+
+    let mut accounts = Vec::with_capacity(touched.len());
+    for pubkey in touched {
+        let account = context
+            .banks_client
+            .get_account(pubkey)
+            .await
+            .map_err(|_| AppError::Internal)?
+            .unwrap_or_default();
+        accounts.push(LocalAccountState {
+            pubkey: pubkey.to_string(),
+            lamports: account.lamports,
+            owner: account.owner.to_string(),
+            executable: account.executable,
+            parsed: decode_account_data(&account.owner, &account.data).ok(),
+            data: base64::engine::general_purpose::STANDARD.encode(&account.data),
+        });
+    }
+
+    let (logs, units_consumed) = result
+        .metadata
+        .map(|metadata| (metadata.log_messages, metadata.compute_units_consumed))
+        .unwrap_or_default();
+
+    let response = LocalSimulationResponse {
+        success: result.result.is_ok(),
+        decoded_error: result
+            .result
+            .as_ref()
+            .err()
+            .and_then(|err| decode_transaction_error(&instructions, err)),
+        err: result.result.err().map(|err| err.to_string()),
+        logs,
+        units_consumed,
+        accounts,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+fn parse_account_seed(seed: &LocalAccountSeed) -> Result<(Pubkey, Account), AppError> {
+    let pubkey = parse_pubkey(&seed.pubkey, "pubkey")?;
+    let owner = match seed.owner.as_deref() {
+        Some(owner) => parse_pubkey(owner, "owner")?,
+        None => system_program::id(),
+    };
+    let data = decode_base64(&seed.data, "data")?;
+
+    let account = Account {
+        lamports: seed.lamports,
+        data,
+        owner,
+        executable: seed.executable,
+        rent_epoch: 0,
+    };
+
+    Ok((pubkey, account))
+}
+
+fn decode_transaction_error(
+    instructions: &[Instruction],
+    err: &TransactionError,
+) -> Option<DecodedInstructionError> {
+    let TransactionError::InstructionError(instruction_index, InstructionError::Custom(code)) = err
+    else {
+        return None;
+    };
+
+    let program_id = instructions.get(*instruction_index as usize)?.program_id;
+    let (name, message) = decode_custom_error(&program_id, *code)?;
+
+    Some(DecodedInstructionError {
+        instruction_index: *instruction_index,
+        program_id: program_id.to_string(),
+        code: *code,
+        name,
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::{json, Value};
+    use solana_sdk::{native_token::LAMPORTS_PER_SOL, program_pack::Pack};
+    use spl_token::state::{Account as TokenAccount, AccountState};
+    use crate::{config::Config, testing};
+    use super::*;
+
+    fn request(body: Value) -> LocalSimulationRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    /// Never executed; the limits are checked first
+    fn any_instruction() -> Value {
+        let program_id = system_program::id().to_string();
+        json!({ "program_id": program_id, "accounts": [], "instruction_data": "" })
+    }
+
+    fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Value {
+        let mut data = vec![0; TokenAccount::LEN];
+        let account = TokenAccount {
+            mint,
+            owner,
+            amount,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        };
+        account.pack_into_slice(&mut data);
+        json!({
+            "lamports": 2_039_280,
+            "owner": spl_token::id().to_string(),
+            "data": base64::engine::general_purpose::STANDARD.encode(data),
+        })
+    }
+
+    async fn simulate(app: &axum::Router, body: Value) -> Value {
+        let response = testing::call(app, testing::post("/simulate/local", body)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        response.body["data"].clone()
+    }
+
+    async fn build(app: &axum::Router, uri: &str, body: Value) -> Value {
+        let response = testing::call(app, testing::post(uri, body)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        response.body["data"].clone()
+    }
+
+    fn account<'a>(simulation: &'a Value, pubkey: &Pubkey) -> &'a Value {
+        let accounts = simulation["accounts"].as_array().unwrap();
+        let pubkey = pubkey.to_string();
+        accounts.iter().find(|account| account["pubkey"] == pubkey).unwrap()
+    }
+
+    #[tokio::test]
+    async fn built_sol_transfers_execute() {
+        let app = testing::app(Config::default());
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let body = json!({ "from": from.to_string(), "to": to.to_string(), "sol": "1" });
+        let instruction = build(&app, "/send/sol", body).await;
+
+        let simulation = simulate(
+            &app,
+            json!({
+                "instructions": [instruction],
+                "signers": [from.to_string()],
+                "accounts": [{ "pubkey": from.to_string(), "lamports": 3 * LAMPORTS_PER_SOL }],
+            }),
+        )
+        .await;
+        assert_eq!(simulation["success"], true, "{}", simulation);
+        assert_eq!(account(&simulation, &from)["lamports"], 2 * LAMPORTS_PER_SOL);
+        assert_eq!(account(&simulation, &to)["lamports"], LAMPORTS_PER_SOL);
+    }
+
+    #[tokio::test]
+    async fn built_token_transfers_execute() {
+        let app = testing::app(Config::default());
+        let (source, destination, owner) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let token_mint = Pubkey::new_unique();
+        // The route names the account debited `mint`
+        let body = json!({
+            "mint": source.to_string(),
+            "destination": destination.to_string(),
+            "owner": owner.to_string(),
+            "amount": 250,
+        });
+        let instruction = build(&app, "/send/token", body).await;
+
+        let mut source_seed = token_account(token_mint, owner, 1_000);
+        source_seed["pubkey"] = source.to_string().into();
+        let mut destination_seed = token_account(token_mint, Pubkey::new_unique(), 0);
+        destination_seed["pubkey"] = destination.to_string().into();
+        let simulation = simulate(
+            &app,
+            json!({
+                "instructions": [instruction],
+                "signers": [owner.to_string()],
+                "accounts": [source_seed, destination_seed],
+            }),
+        )
+        .await;
+        assert_eq!(simulation["success"], true, "{}", simulation);
+        assert_eq!(account(&simulation, &source)["parsed"]["info"]["amount"], "750");
+        assert_eq!(account(&simulation, &destination)["parsed"]["info"]["amount"], "250");
+    }
+
+    #[tokio::test]
+    async fn seeded_account_count_is_limited() {
+        let accounts: Vec<_> = (0..=MAX_SIMULATION_ACCOUNTS)
+            .map(|_| json!({ "pubkey": Pubkey::new_unique().to_string(), "lamports": 1 }))
+            .collect();
+        let req = request(json!({ "instructions": [any_instruction()], "accounts": accounts }));
+
+        let error = simulate_local(ApiJson(req)).await.err().unwrap();
+        assert_eq!(error.code(), ErrorCode::TooManyItems);
+    }
+
+    #[tokio::test]
+    async fn seeded_account_data_is_limited() {
+        let data = base64::engine::general_purpose::STANDARD
+            .encode(vec![0; MAX_SIMULATION_DATA_BYTES / 2 + 1]);
+        let accounts: Vec<_> = (0..2)
+            .map(|_| {
+                json!({ "pubkey": Pubkey::new_unique().to_string(), "lamports": 1, "data": data })
+            })
+            .collect();
+        let req = request(json!({ "instructions": [any_instruction()], "accounts": accounts }));
+
+        let Err(AppError::Invalid(error)) = simulate_local(ApiJson(req)).await else {
+            panic!("expected a field error");
+        };
+        assert_eq!(error.code, ErrorCode::InvalidValue);
+        assert_eq!(error.field.as_deref(), Some("accounts"));
+    }
+}
@@ -1,39 +1,48 @@
-use axum::{extract::Json as AxumJson, Json};
+use axum::{extract::State, Json};
 use base64::Engine;
 use spl_token::instruction as token_instruction;
-use solana_program::pubkey::Pubkey;
 use crate::{
-    error::AppError,
+    error::{AppError, Validator},
+    extract::ApiJson,
+    state::AppState,
+    telemetry,
     types::{
         ApiResponse, TokenCreateRequest, TokenCreateResponse, TokenMintRequest,
         TokenMintResponse, AccountMetaResponse,
     },
-    utils::{validate_amount, validate_different_pubkeys},
+    utils::{parse_pubkey, resolve_token_amount, validate_different_pubkeys},
 };
 
 /// Create a new token mint
+#[utoipa::path(
+    post,
+    path = "/token/create",
+    tag = "token",
+    request_body = TokenCreateRequest,
+    responses((status = 200, body = ApiResponse<TokenCreateResponse>)),
+)]
 pub async fn create_token(
-    AxumJson(req): AxumJson<TokenCreateRequest>,
+    ApiJson(req): ApiJson<TokenCreateRequest>,
 ) -> Result<Json<ApiResponse<TokenCreateResponse>>, AppError> {
-    let mint_authority = req
-        .mint_authority
-        .parse::<Pubkey>()
-        .map_err(|_| AppError::BadRequest("Invalid mintAuthority pubkey".to_string()))?;
-    let mint = req.mint.parse::<Pubkey>()
-        .map_err(|_| AppError::BadRequest("Invalid mint pubkey".to_string()))?;
-
-    // Convert to solana_sdk::pubkey::Pubkey for the instruction
-    let mint_sdk = solana_sdk::pubkey::Pubkey::from(mint.to_bytes());
-    let mint_authority_sdk = solana_sdk::pubkey::Pubkey::from(mint_authority.to_bytes());
+    let mut validator = Validator::default();
+    let mint_authority = validator.check(parse_pubkey(&req.mint_authority, "mintAuthority"));
+    let mint = validator.check(parse_pubkey(&req.mint, "mint"));
+    let (Some(mint_authority), Some(mint)) = (mint_authority, mint) else {
+        return Err(validator.into_error());
+    };
 
     let ix = token_instruction::initialize_mint(
         &spl_token::id(),
-        &mint_sdk,
-        &mint_authority_sdk,
+        &mint,
+        &mint_authority,
         None,
         req.decimals,
     )
-    .map_err(|e| AppError::BadRequest(format!("Failed to create instruction: {}", e)))?;
+    // Only fails for a foreign token program id, which is never passed here
+    .map_err(|err| {
+        tracing::error!(error = %err, "failed to create token instruction");
+        AppError::Internal
+    })?;
 
     let accounts = ix
         .accounts
@@ -53,38 +62,73 @@ pub async fn create_token(
         instruction_data,
     };
 
+    telemetry::record_instruction_built(&response.program_id);
     Ok(Json(ApiResponse::success(response)))
 }
 
 
 /// Mint tokens to a destination account
+#[utoipa::path(
+    post,
+    path = "/token/mint",
+    tag = "token",
+    request_body = TokenMintRequest,
+    responses((status = 200, body = ApiResponse<TokenMintResponse>)),
+)]
 pub async fn mint_token(
-    AxumJson(req): AxumJson<TokenMintRequest>,
+    State(state): State<AppState>,
+    ApiJson(req): ApiJson<TokenMintRequest>,
 ) -> Result<Json<ApiResponse<TokenMintResponse>>, AppError> {
-    let mint = req.mint.parse::<Pubkey>()
-        .map_err(|_| AppError::BadRequest("Invalid mint pubkey".to_string()))?;
-    let destination = req.destination.parse::<Pubkey>()
-        .map_err(|_| AppError::BadRequest("Invalid destination pubkey".to_string()))?;
-    let authority = req.authority.parse::<Pubkey>()
-        .map_err(|_| AppError::BadRequest("Invalid authority pubkey".to_string()))?;
-
-    // Convert to solana_sdk::pubkey::Pubkey for validation and instruction
-    let mint_sdk = solana_sdk::pubkey::Pubkey::from(mint.to_bytes());
-    let dest_sdk = solana_sdk::pubkey::Pubkey::from(destination.to_bytes());
-    let auth_sdk = solana_sdk::pubkey::Pubkey::from(authority.to_bytes());
-    
-    validate_different_pubkeys(&dest_sdk, &auth_sdk, "Destination", "authority")?;
-    validate_amount(req.amount, "Amount")?;
+    // Validate inputs, reporting every failing field at once
+    let mut validator = Validator::default();
+    let mint = validator.check(parse_pubkey(&req.mint, "mint"));
+    let destination = validator.check(parse_pubkey(&req.destination, "destination"));
+    let authority = validator.check(parse_pubkey(&req.authority, "authority"));
+
+    if let (Some(destination), Some(authority)) = (destination, authority) {
+        validator.check(validate_different_pubkeys(
+            &destination,
+            &authority,
+            "Destination",
+            "authority",
+        ));
+    }
+
+    let amount = match mint {
+        Some(mint) => validator.check(
+            resolve_token_amount(
+                &state,
+                &mint,
+                req.amount.as_ref(),
+                req.ui_amount.as_deref(),
+                req.decimals,
+                req.cluster.as_deref(),
+            )
+            .await,
+        ),
+        None => None,
+    };
+
+    let (Some(mint), Some(destination), Some(authority), Some(amount)) =
+        (mint, destination, authority, amount)
+    else {
+        return Err(validator.into_error());
+    };
+    validator.finish()?;
 
     let ix = token_instruction::mint_to(
         &spl_token::id(),
-        &mint_sdk,
-        &dest_sdk,
-        &auth_sdk,
+        &mint,
+        &destination,
+        &authority,
         &[],
-        req.amount,
+        amount,
     )
-    .map_err(|e| AppError::BadRequest(format!("Failed to create instruction: {}", e)))?;
+    // Only fails for a foreign token program id, which is never passed here
+    .map_err(|err| {
+        tracing::error!(error = %err, "failed to create token instruction");
+        AppError::Internal
+    })?;
 
     let accounts = ix
         .accounts
@@ -104,5 +148,6 @@ pub async fn mint_token(
         instruction_data,
     };
 
+    telemetry::record_instruction_built(&response.program_id);
     Ok(Json(ApiResponse::success(response)))
 }
\ No newline at end of file
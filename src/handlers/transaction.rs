@@ -0,0 +1,445 @@
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use axum::{extract::State, Json};
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::Hash,
+    message::Message,
+    pubkey::Pubkey,
+    transaction::{Transaction, VersionedTransaction},
+};
+use crate::{
+    config::{CONFIRMATION_POLL_INTERVAL_MS, MAX_SIMULATION_ACCOUNTS},
+    decode::{decode_custom_error, token_account_amount},
+    error::{AppError, ErrorCode},
+    extract::ApiJson,
+    rpc::{Commitment, RpcAccount, RpcEndpoint, RpcResponse},
+    state::AppState,
+    types::{
+        ApiResponse, DecodedInstructionError, SendTransactionRequest, SendTransactionResponse,
+        SimulateTransactionRequest, SimulateTransactionResponse, SimulatedAccountBalance,
+        SimulationReturnData, TransactionBuildRequest, TransactionBuildResponse,
+    },
+    utils::{parse_instruction, parse_pubkey, parse_transaction, validate_not_empty},
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcSignatureStatus {
+    slot: u64,
+    confirmations: Option<u64>,
+    err: Option<Value>,
+    confirmation_status: Option<Commitment>,
+}
+
+/// Assemble an unsigned transaction, fetching the blockhash and fee from the RPC as needed
+#[utoipa::path(
+    post,
+    path = "/transaction/build",
+    tag = "transaction",
+    request_body = TransactionBuildRequest,
+    responses((status = 200, body = ApiResponse<TransactionBuildResponse>)),
+)]
+pub async fn build_transaction(
+    State(state): State<AppState>,
+    ApiJson(req): ApiJson<TransactionBuildRequest>,
+) -> Result<Json<ApiResponse<TransactionBuildResponse>>, AppError> {
+    validate_not_empty(&req.fee_payer, "fee_payer")?;
+
+    if req.instructions.is_empty() {
+        return Err(AppError::invalid(
+            ErrorCode::MissingField,
+            "instructions",
+            "At least one instruction is required",
+        ));
+    }
+
+    let fee_payer = parse_pubkey(&req.fee_payer, "fee_payer")?;
+    let signers = req
+        .signers
+        .iter()
+        .map(|signer| parse_pubkey(signer, "signers"))
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    let instructions = req
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| {
+            parse_instruction(instruction, &signers)
+                .map_err(|e| e.in_item("Instruction", "instructions", index))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let rpc = state.rpc.optional_endpoint(req.cluster.as_deref())?;
+
+    let (blockhash, last_valid_block_height) = match (&req.recent_blockhash, &rpc) {
+        (Some(blockhash), _) => {
+            let blockhash = Hash::from_str(blockhash).map_err(|_| {
+                AppError::invalid(
+                    ErrorCode::InvalidBase58,
+                    "recent_blockhash",
+                    "Invalid recent_blockhash: expected base58 hash",
+                )
+            })?;
+            (blockhash, None)
+        }
+        (None, Some(rpc)) => {
+            let latest = rpc.latest_blockhash(req.commitment).await?;
+            let blockhash = Hash::from_str(&latest.blockhash).map_err(|_| {
+                AppError::Rpc(format!(
+                    "getLatestBlockhash returned an invalid hash: {}",
+                    latest.blockhash
+                ))
+            })?;
+            (blockhash, Some(latest.last_valid_block_height))
+        }
+        (None, None) => {
+            return Err(AppError::invalid(
+                ErrorCode::MissingField,
+                "recent_blockhash",
+                "recent_blockhash is required when no RPC endpoint is configured",
+            ))
+        }
+    };
+
+    let message = Message::new_with_blockhash(&instructions, Some(&fee_payer), &blockhash);
+    let message_bytes = message.serialize();
+    let message_b64 = base64::engine::general_purpose::STANDARD.encode(&message_bytes);
+
+    // The fee is null when the blockhash has already expired
+    let fee = match &rpc {
+        Some(rpc) => {
+            let fee: RpcResponse<Option<u64>> = rpc
+                .call(
+                    "getFeeForMessage",
+                    json!([message_b64, { "commitment": req.commitment }]),
+                )
+                .await?;
+            fee.value
+        }
+        None => None,
+    };
+
+    let signers = message.account_keys[..message.header.num_required_signatures as usize]
+        .iter()
+        .map(|pubkey| pubkey.to_string())
+        .collect();
+
+    let transaction = Transaction::new_unsigned(message);
+    let transaction_bytes = bincode::serialize(&transaction).map_err(|_| AppError::Internal)?;
+
+    let response = TransactionBuildResponse {
+        transaction: base64::engine::general_purpose::STANDARD.encode(transaction_bytes),
+        message: message_b64,
+        recent_blockhash: blockhash.to_string(),
+        last_valid_block_height,
+        fee,
+        signers,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Submit a signed transaction and wait for the requested commitment
+///
+/// Once the RPC accepts the transaction the response is always a success carrying its
+/// signature; `expired`, `timed_out` or `poll_error` tell when it was not seen to settle.
+#[utoipa::path(
+    post,
+    path = "/transaction/send",
+    tag = "transaction",
+    request_body = SendTransactionRequest,
+    responses((status = 200, body = ApiResponse<SendTransactionResponse>)),
+)]
+pub async fn send_transaction(
+    State(state): State<AppState>,
+    ApiJson(req): ApiJson<SendTransactionRequest>,
+) -> Result<Json<ApiResponse<SendTransactionResponse>>, AppError> {
+    let transaction = parse_transaction(&req.transaction)?;
+
+    if let Some(index) = transaction
+        .verify_with_results()
+        .iter()
+        .position(|valid| !valid)
+    {
+        return Err(AppError::invalid(
+            ErrorCode::InvalidSignature,
+            "transaction",
+            format!("Transaction signature {} does not verify", index),
+        ));
+    }
+
+    let blockhash = transaction.message.recent_blockhash().to_string();
+    let rpc = state.rpc.endpoint(req.cluster.as_deref())?;
+
+    let mut config = json!({
+        "encoding": "base64",
+        "skipPreflight": req.skip_preflight,
+        "preflightCommitment": req.commitment,
+    });
+    if let Some(max_retries) = req.max_retries {
+        config["maxRetries"] = json!(max_retries);
+    }
+
+    let signature: String = rpc
+        .call("sendTransaction", json!([req.transaction, config]))
+        .await?;
+
+    // The transaction is out now, so whatever happens while waiting the caller gets its
+    // signature back rather than an error that invites a second submission
+    let timeout = Duration::from_secs(state.config.rpc.confirmation_timeout_secs);
+    let (status, confirmation) =
+        await_confirmation(&rpc, &signature, &blockhash, req.commitment, timeout).await;
+    let poll_error = match &confirmation {
+        Confirmation::PollFailed(err) => {
+            tracing::warn!(%signature, error = %err, "lost track of a submitted transaction");
+            Some(err.to_string())
+        }
+        _ => None,
+    };
+
+    let response = SendTransactionResponse {
+        signature,
+        confirmation_status: status.as_ref().and_then(|s| s.confirmation_status),
+        slot: status.as_ref().map(|s| s.slot),
+        confirmations: status.as_ref().and_then(|s| s.confirmations),
+        err: status.and_then(|s| s.err),
+        expired: matches!(confirmation, Confirmation::Expired),
+        timed_out: matches!(confirmation, Confirmation::TimedOut),
+        poll_error,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// How waiting for a submitted transaction ended
+enum Confirmation {
+    /// It reached the requested commitment or failed on chain
+    Settled,
+    /// Its blockhash expired first
+    Expired,
+    TimedOut,
+    PollFailed(AppError),
+}
+
+/// Poll until the transaction settles, its blockhash expires or `timeout` passes; returns the
+/// last status seen
+async fn await_confirmation(
+    rpc: &RpcEndpoint,
+    signature: &str,
+    blockhash: &str,
+    commitment: Commitment,
+    timeout: Duration,
+) -> (Option<RpcSignatureStatus>, Confirmation) {
+    let deadline = Instant::now() + timeout;
+    let mut status = None;
+
+    let confirmation = async {
+        loop {
+            status = signature_status(rpc, signature).await?;
+            if status
+                .as_ref()
+                .is_some_and(|s| s.err.is_some() || s.confirmation_status >= Some(commitment))
+            {
+                return Ok(Confirmation::Settled);
+            }
+
+            let valid: RpcResponse<bool> = rpc
+                .call(
+                    "isBlockhashValid",
+                    json!([blockhash, { "commitment": Commitment::Processed }]),
+                )
+                .await?;
+            if !valid.value {
+                // The transaction may have landed between the two calls, so look once more
+                status = signature_status(rpc, signature).await?;
+                return Ok(Confirmation::Expired);
+            }
+
+            if Instant::now() >= deadline {
+                return Ok(Confirmation::TimedOut);
+            }
+
+            tokio::time::sleep(Duration::from_millis(CONFIRMATION_POLL_INTERVAL_MS)).await;
+        }
+    }
+    .await
+    .unwrap_or_else(Confirmation::PollFailed);
+
+    (status, confirmation)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcSimulateResult {
+    err: Option<Value>,
+    logs: Option<Vec<String>>,
+    accounts: Option<Vec<Option<RpcAccount>>>,
+    units_consumed: Option<u64>,
+    return_data: Option<RpcReturnData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcReturnData {
+    program_id: String,
+    data: (String, String),
+}
+
+/// Simulate a transaction and report logs, compute usage and balance changes
+#[utoipa::path(
+    post,
+    path = "/transaction/simulate",
+    tag = "transaction",
+    request_body = SimulateTransactionRequest,
+    responses((status = 200, body = ApiResponse<SimulateTransactionResponse>)),
+)]
+pub async fn simulate_transaction(
+    State(state): State<AppState>,
+    ApiJson(req): ApiJson<SimulateTransactionRequest>,
+) -> Result<Json<ApiResponse<SimulateTransactionResponse>>, AppError> {
+    let transaction = parse_transaction(&req.transaction)?;
+
+    if req.sig_verify && req.replace_recent_blockhash {
+        return Err(AppError::request(
+            ErrorCode::ConflictingFields,
+            "sig_verify and replace_recent_blockhash cannot both be set",
+        ));
+    }
+
+    if req.accounts.len() > MAX_SIMULATION_ACCOUNTS {
+        return Err(AppError::invalid(
+            ErrorCode::TooManyItems,
+            "accounts",
+            format!(
+                "Too many accounts: expected at most {}, got {}",
+                MAX_SIMULATION_ACCOUNTS,
+                req.accounts.len()
+            ),
+        ));
+    }
+
+    let addresses = req
+        .accounts
+        .iter()
+        .map(|address| parse_pubkey(address, "accounts").map(|pubkey| pubkey.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let rpc = state.rpc.endpoint(req.cluster.as_deref())?;
+
+    let simulate = rpc.call::<RpcResponse<RpcSimulateResult>>(
+        "simulateTransaction",
+        json!([
+            req.transaction,
+            {
+                "encoding": "base64",
+                "sigVerify": req.sig_verify,
+                "replaceRecentBlockhash": req.replace_recent_blockhash,
+                "commitment": req.commitment,
+                "accounts": { "addresses": addresses, "encoding": "base64" },
+            },
+        ]),
+    );
+    let (pre_accounts, simulation) = if addresses.is_empty() {
+        (Vec::new(), simulate.await?)
+    } else {
+        let pre_accounts = rpc.call::<RpcResponse<Vec<Option<RpcAccount>>>>(
+            "getMultipleAccounts",
+            json!([addresses, { "encoding": "base64", "commitment": req.commitment }]),
+        );
+        let (pre_accounts, simulation) = tokio::try_join!(pre_accounts, simulate)?;
+        (pre_accounts.value, simulation)
+    };
+
+    let result = simulation.value;
+    let post_accounts = result.accounts.unwrap_or_default();
+
+    let accounts = addresses
+        .into_iter()
+        .enumerate()
+        .map(|(index, address)| {
+            let pre = pre_accounts.get(index).and_then(Option::as_ref);
+            let post = post_accounts.get(index).and_then(Option::as_ref);
+            SimulatedAccountBalance {
+                address,
+                pre_lamports: pre.map(|account| account.lamports),
+                post_lamports: post.map(|account| account.lamports),
+                pre_token_amount: pre.and_then(account_token_amount),
+                post_token_amount: post.and_then(account_token_amount),
+            }
+        })
+        .collect();
+
+    let return_data = result.return_data.map(|return_data| SimulationReturnData {
+        program_id: return_data.program_id,
+        data: return_data.data.0,
+    });
+
+    let response = SimulateTransactionResponse {
+        slot: simulation.context.slot,
+        decoded_error: result
+            .err
+            .as_ref()
+            .and_then(|err| decode_instruction_error(&transaction, err)),
+        err: result.err,
+        logs: result.logs.unwrap_or_default(),
+        units_consumed: result.units_consumed,
+        return_data,
+        accounts,
+    };
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+fn account_token_amount(account: &RpcAccount) -> Option<String> {
+    let owner = account.owner.parse::<Pubkey>().ok()?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(&account.data.0)
+        .ok()?;
+    token_account_amount(&owner, &data).map(|amount| amount.to_string())
+}
+
+/// Translate `{"InstructionError": [index, {"Custom": code}]}` into a readable error
+fn decode_instruction_error(
+    transaction: &VersionedTransaction,
+    err: &Value,
+) -> Option<DecodedInstructionError> {
+    let instruction_error = err.get("InstructionError")?.as_array()?;
+    let instruction_index = u8::try_from(instruction_error.first()?.as_u64()?).ok()?;
+    let code = u32::try_from(instruction_error.get(1)?.get("Custom")?.as_u64()?).ok()?;
+
+    let instruction = transaction
+        .message
+        .instructions()
+        .get(instruction_index as usize)?;
+    let program_id = transaction
+        .message
+        .static_account_keys()
+        .get(instruction.program_id_index as usize)?;
+
+    let (name, message) = decode_custom_error(program_id, code)?;
+
+    Some(DecodedInstructionError {
+        instruction_index,
+        program_id: program_id.to_string(),
+        code,
+        name,
+        message,
+    })
+}
+
+async fn signature_status(
+    rpc: &RpcEndpoint,
+    signature: &str,
+) -> Result<Option<RpcSignatureStatus>, AppError> {
+    let statuses: RpcResponse<Vec<Option<RpcSignatureStatus>>> = rpc
+        .call("getSignatureStatuses", json!([[signature]]))
+        .await?;
+    Ok(statuses.value.into_iter().next().flatten())
+}
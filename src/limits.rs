@@ -0,0 +1,321 @@
+use std::{any::Any, sync::Arc, time::Duration};
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::header::CONTENT_LENGTH,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body_util::Limited;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use crate::{
+    auth::route_matches,
+    config::Config,
+    dispatch::Dispatched,
+    error::{AppError, ErrorCode},
+    state::AppState,
+};
+
+/// Concurrency permits for the whole server and for each `limits.routes` rule
+pub struct RequestLimits {
+    global: Arc<Semaphore>,
+    /// Indexed like `limits.routes`; `None` for rules without `max_concurrent`
+    routes: Vec<Option<Arc<Semaphore>>>,
+}
+
+impl RequestLimits {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(config.limits.max_concurrent_requests)),
+            routes: config
+                .limits
+                .routes
+                .iter()
+                .map(|rule| rule.max_concurrent.map(|max| Arc::new(Semaphore::new(max))))
+                .collect(),
+        }
+    }
+
+    /// Permits for every limit that applies to the route, or `None` when one is exhausted.
+    ///
+    /// Dispatched calls run within a request that already holds a global permit, so they
+    /// only take route permits, and wait for them rather than shedding their own batch.
+    async fn acquire(
+        &self,
+        config: &Config,
+        route: &str,
+        dispatched: bool,
+    ) -> Option<Vec<OwnedSemaphorePermit>> {
+        let route_semaphores = config
+            .limits
+            .routes
+            .iter()
+            .zip(&self.routes)
+            .filter(|(rule, _)| route_matches(&rule.route, route))
+            .filter_map(|(_, semaphore)| semaphore.as_ref());
+
+        if dispatched {
+            let mut permits = Vec::new();
+            for semaphore in route_semaphores {
+                permits.push(Arc::clone(semaphore).acquire_owned().await.ok()?);
+            }
+            return Some(permits);
+        }
+
+        std::iter::once(&self.global)
+            .chain(route_semaphores)
+            .map(|semaphore| Arc::clone(semaphore).try_acquire_owned().ok())
+            .collect()
+    }
+}
+
+/// Shed load beyond the concurrency limits, cap the body size and time out slow requests
+pub async fn enforce_limits(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let config = &state.config;
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or_else(|| req.uri().path())
+        .to_string();
+
+    let dispatched = req.extensions().get::<Dispatched>().is_some();
+    let permits = state.limits.acquire(config, &route, dispatched).await;
+    let _permits = permits.ok_or_else(|| {
+        AppError::request(ErrorCode::Overloaded, "Server is at capacity; retry shortly")
+    })?;
+
+    let body_limit = config
+        .limits
+        .body_limit_bytes(&route)
+        .unwrap_or(config.server.body_limit_bytes);
+    let declared_length = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared_length.is_some_and(|length| length > body_limit as u64) {
+        return Err(body_too_large(body_limit));
+    }
+    // Bodies without a length are cut off once they exceed the limit while being read
+    let req = req.map(|body| Body::new(Limited::new(body, body_limit)));
+
+    let timeout = config.timeout_secs(&route);
+    tokio::time::timeout(Duration::from_secs(timeout), next.run(req))
+        .await
+        .map_err(|_| {
+            AppError::request(
+                ErrorCode::Timeout,
+                format!("Request did not complete within {} s", timeout),
+            )
+        })
+}
+
+fn body_too_large(limit: usize) -> AppError {
+    AppError::request(
+        ErrorCode::PayloadTooLarge,
+        format!("Request body exceeds the {} byte limit", limit),
+    )
+}
+
+/// Answer a panicking handler with the usual internal error envelope
+pub fn panic_response(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| panic.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic");
+    tracing::error!(panic = message, "handler panicked");
+
+    AppError::Internal.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{header::RETRY_AFTER, StatusCode},
+        routing::post,
+        Router,
+    };
+    use serde_json::json;
+    use tokio::sync::Notify;
+    use crate::{
+        config::{LimitsConfig, RouteLimitsOverride},
+        dispatch, routes, testing,
+    };
+    use super::*;
+
+    fn app(rules: Vec<RouteLimitsOverride>, test_routes: Router<AppState>) -> Router {
+        let mut config = Config::default();
+        config.limits.routes.extend(rules);
+        let state = AppState::new(config, None);
+        routes::with_middleware(routes::api_routes(&state.config).merge(test_routes), &state)
+    }
+
+    fn rule(route: &str) -> RouteLimitsOverride {
+        RouteLimitsOverride {
+            route: route.to_string(),
+            ..RouteLimitsOverride::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_rejected_with_413() {
+        let app = app(
+            vec![RouteLimitsOverride {
+                body_limit_bytes: Some(16),
+                ..rule("/calc/rent")
+            }],
+            Router::new(),
+        );
+        let body = json!({ "data_len": 0, "padding": "x".repeat(64) });
+
+        // Declared up front
+        let mut req = testing::post("/calc/rent", body.clone());
+        req.headers_mut().insert(CONTENT_LENGTH, body.to_string().len().into());
+        let response = testing::call(&app, req).await;
+        assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response.body["code"], "PAYLOAD_TOO_LARGE");
+
+        // Found while reading a body of unknown length
+        let response = testing::call(&app, testing::post("/calc/rent", body)).await;
+        assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = testing::call(&app, testing::post("/calc/fee", json!({}))).await;
+        assert_ne!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn slow_requests_time_out_with_504() {
+        let slow = Router::new().route(
+            "/test/slow",
+            post(|| async { tokio::time::sleep(Duration::from_secs(5)).await }),
+        );
+        let app = app(
+            vec![RouteLimitsOverride {
+                timeout_secs: Some(1),
+                ..rule("/test/slow")
+            }],
+            slow,
+        );
+
+        let response = testing::call(&app, testing::post("/test/slow", json!({}))).await;
+        assert_eq!(response.status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(response.body["code"], "TIMEOUT");
+    }
+
+    #[tokio::test]
+    async fn requests_beyond_the_route_limit_are_shed_with_503() {
+        let entered = Arc::new(Notify::new());
+        let release = Arc::new(Notify::new());
+        let held = Router::new().route(
+            "/test/held",
+            post({
+                let (entered, release) = (entered.clone(), release.clone());
+                move || async move {
+                    entered.notify_one();
+                    release.notified().await;
+                }
+            }),
+        );
+        let app = app(
+            vec![RouteLimitsOverride {
+                max_concurrent: Some(1),
+                ..rule("/test/held")
+            }],
+            held,
+        );
+
+        let first = tokio::spawn({
+            let app = app.clone();
+            async move { testing::call(&app, testing::post("/test/held", json!({}))).await }
+        });
+        entered.notified().await;
+
+        let response = testing::call(&app, testing::post("/test/held", json!({}))).await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.body["code"], "OVERLOADED");
+        assert_eq!(response.headers[RETRY_AFTER], "1");
+
+        // Other routes are unaffected, and the permit comes back afterwards
+        let response = testing::call(&app, testing::post("/calc/rent", json!({ "data_len": 0 })));
+        assert_eq!(response.await.status, StatusCode::OK);
+        release.notify_one();
+        assert_eq!(first.await.unwrap().status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn batched_operations_wait_for_route_permits() {
+        let entered = Arc::new(Notify::new());
+        let release = Arc::new(Notify::new());
+        let held = Router::new().route(
+            "/calc/held",
+            post({
+                let (entered, release) = (entered.clone(), release.clone());
+                move || async move {
+                    entered.notify_one();
+                    release.notified().await;
+                }
+            }),
+        );
+        let rules = vec![RouteLimitsOverride {
+            max_concurrent: Some(1),
+            ..rule("/calc/*")
+        }];
+        let app = app(rules.clone(), held);
+
+        let first = tokio::spawn({
+            let app = app.clone();
+            async move { testing::call(&app, testing::post("/calc/held", json!({}))).await }
+        });
+        entered.notified().await;
+        let rent = json!({ "data_len": 0 });
+        let response = testing::call(&app, testing::post("/calc/rent", rent.clone())).await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+
+        // The batch holds the only global permit it needs, and its operations queue for
+        // the route's instead of being shed
+        let operation = json!({ "route": "/calc/rent", "body": rent });
+        let batch = json!({ "operations": vec![operation; 20], "concurrency": 16 });
+        let batch = tokio::spawn({
+            let app = app.clone();
+            async move { testing::call(&app, testing::post("/batch", batch)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        release.notify_one();
+        assert_eq!(first.await.unwrap().status, StatusCode::OK);
+
+        let response = batch.await.unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["data"]["succeeded"], 20);
+
+        let config = Config {
+            limits: LimitsConfig {
+                routes: rules,
+                ..LimitsConfig::default()
+            },
+            ..Config::default()
+        };
+        assert_eq!(dispatch::concurrency(&config, ["/calc/rent", "/keypair"]), 1);
+        assert_eq!(dispatch::concurrency(&config, ["/keypair"]), 16);
+    }
+
+    #[tokio::test]
+    async fn panics_become_internal_errors() {
+        async fn boom() {
+            panic!("boom")
+        }
+        let panicking = Router::new().route("/test/panic", post(boom));
+        let app = app(Vec::new(), panicking);
+
+        let response = testing::call(&app, testing::post("/test/panic", json!({}))).await;
+        assert_eq!(response.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.body["code"], "INTERNAL");
+        assert_eq!(response.body["error"], "Internal server error");
+        assert!(response.header("x-request-id").is_some());
+    }
+}
@@ -1,46 +1,133 @@
+mod auth;
 mod config;
+mod decode;
+mod dispatch;
 mod error;
+mod extract;
 mod routes;
 mod handlers;
+mod limits;
+mod openapi;
+mod ratelimit;
+mod rpc;
+mod server;
+mod state;
+mod telemetry;
+#[cfg(test)]
+mod testing;
+mod tls;
 mod types;
 mod utils;
 
-use axum::Router;
+use clap::Parser;
 use std::net::SocketAddr;
-use tower_http::cors::{CorsLayer, Any};
-use axum::http::Method;
-use std::env;
+use std::process;
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() {
+    let cli = config::Cli::parse();
+    let config = match config::Config::load(&cli) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    };
+
+    if cli.print_config {
+        print!("{}", toml::to_string_pretty(&config).expect("configuration serializes to TOML"));
+        return;
+    }
+
+    let tls = if config.tls.enabled() {
+        match tls::TlsReloader::new(&config.tls) {
+            Ok(tls) => Some(tls),
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(2);
+            }
+        }
+    } else {
+        None
+    };
+
     // Initialize logging
-    println!("Starting Solana HTTP Server...");
-
-    // Configure CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST])
-        .allow_headers(Any);
-
-    // Build the application with all routes
-    let app = Router::new()
-        .merge(routes::health_routes())
-        .merge(routes::keypair_routes())
-        .merge(routes::token_routes())
-        .merge(routes::message_routes())
-        .merge(routes::send_routes())
-        .layer(cors);
-
-    // Get port from environment or default to 3000
-    let port = env::var("PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(3000);
-    
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    println!("🚀 Server listening on {}", addr);
+    telemetry::init(&config.logging);
+    tracing::info!("Starting Solana HTTP Server...");
+
+    let metrics = config.metrics.enabled.then(telemetry::init_metrics);
+    let metrics_addr = config
+        .metrics
+        .port
+        .filter(|_| config.metrics.enabled)
+        .map(|port| SocketAddr::new(config.server.bind_address, port));
+
+    let state = state::AppState::new(config, metrics);
+    if let Some(limiter) = &state.rate_limiter {
+        limiter.spawn_maintenance();
+    }
+
+    // Bind every listener before serving, so a taken port stops startup
+    let admin_listener = match metrics_addr {
+        Some(metrics_addr) => match tokio::net::TcpListener::bind(metrics_addr).await {
+            Ok(listener) => Some(listener),
+            Err(err) => {
+                tracing::error!(%metrics_addr, error = %err, "failed to bind the metrics port");
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let app = routes::app(&state);
+
+    if let Some(tls) = &tls {
+        tls.spawn_reload();
+    }
+    let listener = match server::Listener::bind(&state.config.server, tls).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            tracing::error!(error = %err, "failed to bind the server address");
+            process::exit(1);
+        }
+    };
+    tracing::info!(%listener, "Server listening");
+
+    // Both servers stop on the same signal
+    let (stop, mut stopped) = tokio::sync::watch::channel(false);
+
+    // Serve metrics on the admin port, if one was configured
+    let admin = admin_listener.map(|listener| {
+        let admin = routes::metrics_routes().with_state(state.clone());
+        if let Ok(metrics_addr) = listener.local_addr() {
+            tracing::info!(%metrics_addr, "Metrics listening");
+        }
+        tokio::spawn(async move {
+            let shutdown = async move {
+                let _ = stopped.wait_for(|stopped| *stopped).await;
+            };
+            if let Err(err) = axum::serve(listener, admin).with_graceful_shutdown(shutdown).await {
+                tracing::error!(error = %err, "metrics server failed");
+            }
+        })
+    });
 
     // Start the server
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
-}
\ No newline at end of file
+    let lifecycle = state.lifecycle.clone();
+    let shutdown = async move {
+        server::shutdown_signal().await;
+        lifecycle.start_draining();
+        let _ = stop.send(true);
+    };
+    let drain_timeout = Duration::from_secs(state.config.server.shutdown_timeout_secs);
+    server::serve(listener, app, shutdown, drain_timeout).await;
+    if let Some(admin) = admin {
+        let _ = admin.await;
+    }
+
+    if let Some(limiter) = &state.rate_limiter {
+        limiter.persist();
+    }
+    tracing::info!("Server stopped");
+}
@@ -0,0 +1,206 @@
+use utoipa::{
+    openapi::{self, ContentBuilder, Ref, RefOr, ResponseBuilder},
+    Modify, OpenApi, ToSchema,
+};
+use crate::{
+    error::{ErrorCode, FieldError},
+    handlers,
+};
+
+/// OpenAPI 3.1 document covering every route in `routes.rs`
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Solana Rust API",
+        description = "Keypair, signing, instruction building and cluster access over HTTP. \
+                       Every response uses the `{ success, data | error }` envelope."
+    ),
+    paths(
+        handlers::health::health_check,
+        handlers::health::liveness,
+        handlers::health::readiness,
+        handlers::keypair::generate_keypair,
+        handlers::keypair::generate_secp256k1_keypair,
+        handlers::token::create_token,
+        handlers::token::mint_token,
+        handlers::message::sign_message,
+        handlers::message::verify_message,
+        handlers::message::verify_message_batch,
+        handlers::send::send_sol,
+        handlers::send::send_token,
+        handlers::instruction::build_ed25519_instruction,
+        handlers::instruction::build_secp256k1_instruction,
+        handlers::ethereum::derive_eth_address,
+        handlers::cluster::get_cluster_info,
+        handlers::account_info::get_account,
+        handlers::account_info::get_token_holdings,
+        handlers::transaction::build_transaction,
+        handlers::transaction::send_transaction,
+        handlers::transaction::simulate_transaction,
+        handlers::simulate::simulate_local,
+        handlers::decode::decode_account,
+        handlers::calc::calc_rent,
+        handlers::calc::calc_fee,
+        handlers::jsonrpc::handle_rpc,
+        handlers::batch::run_batch,
+        handlers::metrics::render_metrics,
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&ErrorResponses),
+    tags(
+        (name = "health", description = "Liveness and readiness"),
+        (name = "keypair", description = "Key generation"),
+        (name = "token", description = "SPL Token instruction builders"),
+        (name = "message", description = "Ed25519 message signing and verification"),
+        (name = "send", description = "SOL and token transfer instruction builders"),
+        (name = "instruction", description = "Signature verification precompile instructions"),
+        (name = "ethereum", description = "Ethereum interoperability"),
+        (name = "cluster", description = "Cluster information over RPC"),
+        (name = "account", description = "Account reads over RPC"),
+        (name = "transaction", description = "Transaction building, submission and simulation"),
+        (name = "simulate", description = "Offline execution against a local bank"),
+        (name = "decode", description = "Offline account data decoding"),
+        (name = "calc", description = "Offline rent and fee calculators"),
+        (name = "rpc", description = "JSON-RPC 2.0 access to every operation"),
+        (name = "batch", description = "Many operations in one request"),
+        (name = "metrics", description = "Prometheus metrics"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Body of every 4xx and 5xx response
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct ErrorResponse {
+    /// Always false
+    success: bool,
+    error: String,
+    code: ErrorCode,
+    /// Request field the error refers to, when it concerns a single field
+    field: Option<String>,
+    /// Every failure, when several fields failed validation
+    errors: Option<Vec<FieldError>>,
+    /// Echoes the `X-Request-Id` of the request
+    request_id: Option<String>,
+}
+
+/// Attaches the shared error envelope to every operation
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let content = ContentBuilder::new()
+            .schema(Some(Ref::from_schema_name("ErrorResponse")))
+            .build();
+        let response = ResponseBuilder::new()
+            .description("Error envelope; `code` identifies the failure")
+            .content("application/json", content)
+            .build();
+
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .responses
+            .insert("Error".to_string(), RefOr::T(response));
+
+        for item in openapi.paths.paths.values_mut() {
+            for operation in [&mut item.get, &mut item.post].into_iter().flatten() {
+                for status in ["4XX", "5XX"] {
+                    operation
+                        .responses
+                        .responses
+                        .insert(status.to_string(), RefOr::Ref(Ref::from_response_name("Error")));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+    };
+    use tower::Service;
+    use crate::{config::Config, dispatch::OPERATIONS, routes, state::AppState};
+    use super::*;
+
+    /// Routes served besides the operations
+    const OTHER_ROUTES: &[(&str, &str)] = &[
+        ("get", "/health"),
+        ("get", "/health/live"),
+        ("get", "/health/ready"),
+        ("get", "/metrics"),
+        ("post", "/rpc"),
+        ("post", "/batch"),
+    ];
+
+    /// `(method, path)` of every route, in OpenAPI path syntax
+    fn served_routes() -> Vec<(String, String)> {
+        let operations = OPERATIONS.iter().map(|operation| {
+            let path = operation
+                .route
+                .split('/')
+                .map(|segment| match segment.strip_prefix(':') {
+                    Some(param) => format!("{{{}}}", param),
+                    None => segment.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            (operation.method.as_str().to_lowercase(), path)
+        });
+        let others = OTHER_ROUTES
+            .iter()
+            .map(|(method, path)| (method.to_string(), path.to_string()));
+        operations.chain(others).collect()
+    }
+
+    /// Every documented `(method, path)`
+    fn documented_routes() -> Vec<(String, String)> {
+        let mut routes = Vec::new();
+        for (path, item) in ApiDoc::openapi().paths.paths {
+            if item.get.is_some() {
+                routes.push(("get".to_string(), path.clone()));
+            }
+            if item.post.is_some() {
+                routes.push(("post".to_string(), path));
+            }
+        }
+        routes
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let mut served = served_routes();
+        let mut documented = documented_routes();
+        served.sort();
+        documented.sort();
+        assert_eq!(served, documented);
+    }
+
+    #[tokio::test]
+    async fn every_documented_route_is_served() {
+        let mut config = Config::default();
+        config.features.docs = true;
+        config.features.local_simulation = true;
+        config.metrics.enabled = true;
+        config.metrics.port = None;
+        let mut app = routes::app(&AppState::new(config, None));
+
+        // Probe with the other method, so the route answers without running its handler
+        for (method, path) in documented_routes() {
+            let probe = if method == "get" { Method::POST } else { Method::GET };
+            let uri = path.replace("{pubkey}", "11111111111111111111111111111111");
+            let req = Request::builder().method(probe).uri(&uri).body(Body::empty()).unwrap();
+            let response = app.call(req).await.unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "{} {} is not served",
+                method,
+                path
+            );
+        }
+    }
+}
@@ -0,0 +1,550 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header::RETRY_AFTER, HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use crate::{
+    auth::{route_matches, AuthenticatedKey},
+    config::{BucketConfig, RateLimitConfig, QUOTA_FLUSH_INTERVAL_SECS},
+    error::{AppError, ErrorCode},
+    state::AppState,
+};
+
+pub const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+pub const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+pub const RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+const SECS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Client {
+    Ip(IpAddr),
+    Key(String),
+}
+
+impl Client {
+    fn describe(&self) -> &'static str {
+        match self {
+            Client::Ip(_) => "IP address",
+            Client::Key(_) => "API key",
+        }
+    }
+}
+
+/// A bucket is identified by the rule it enforces (0 for the default, `i + 1` for
+/// `routes[i]`) and the client it belongs to
+type BucketId = (usize, Client);
+
+struct Bucket {
+    limit: BucketConfig,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: BucketConfig, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: f64::from(limit.burst),
+            updated: now,
+        }
+    }
+
+    fn refill_rate(&self) -> f64 {
+        f64::from(self.limit.per_minute) / 60.0
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_rate()).min(f64::from(self.limit.burst));
+        self.updated = now;
+    }
+
+    /// Seconds until the next request would be admitted
+    fn retry_after(&self) -> u64 {
+        ((1.0 - self.tokens) / self.refill_rate()).ceil().max(1.0) as u64
+    }
+
+    fn usage(&self) -> Usage {
+        let missing = f64::from(self.limit.burst) - self.tokens;
+        Usage {
+            limit: self.limit.burst,
+            remaining: self.tokens.floor() as u32,
+            reset_secs: (missing / self.refill_rate()).ceil() as u64,
+        }
+    }
+}
+
+/// What the `X-RateLimit-*` headers report: the bucket closest to running out
+#[derive(Debug, Clone, Copy)]
+struct Usage {
+    limit: u32,
+    remaining: u32,
+    /// Seconds until the bucket is full again
+    reset_secs: u64,
+}
+
+impl Usage {
+    fn apply(self, headers: &mut HeaderMap) {
+        headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(self.limit));
+        headers.insert(RATE_LIMIT_REMAINING, HeaderValue::from(self.remaining));
+        headers.insert(RATE_LIMIT_RESET, HeaderValue::from(self.reset_secs));
+    }
+}
+
+enum Rejection {
+    RateLimited { usage: Usage, retry_after: u64 },
+    QuotaExceeded { client: &'static str, quota: u64, retry_after: u64 },
+}
+
+impl Rejection {
+    fn into_response(self) -> Response {
+        let (error, usage, retry_after) = match self {
+            Rejection::RateLimited { usage, retry_after } => {
+                let message = format!("Rate limit exceeded; retry in {} s", retry_after);
+                (AppError::request(ErrorCode::RateLimited, message), Some(usage), retry_after)
+            }
+            Rejection::QuotaExceeded {
+                client,
+                quota,
+                retry_after,
+            } => {
+                let message = format!(
+                    "Daily quota of {} requests exceeded for this {}; it resets at 00:00 UTC",
+                    quota, client
+                );
+                (AppError::request(ErrorCode::QuotaExceeded, message), None, retry_after)
+            }
+        };
+
+        let mut response = error.into_response();
+        let headers = response.headers_mut();
+        headers.insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        if let Some(usage) = usage {
+            usage.apply(headers);
+        }
+        response
+    }
+}
+
+/// Requests counted against the daily quotas, as persisted in `quota_file`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QuotaUsage {
+    /// Days since the Unix epoch, in UTC
+    day: u64,
+    ip: HashMap<IpAddr, u64>,
+    key: HashMap<String, u64>,
+    /// Changed since it was last written
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl QuotaUsage {
+    fn roll_over(&mut self, day: u64) {
+        if self.day != day {
+            *self = Self {
+                day,
+                dirty: true,
+                ..Self::default()
+            };
+        }
+    }
+
+    fn counter(&mut self, client: &Client) -> &mut u64 {
+        match client {
+            Client::Ip(ip) => self.ip.entry(*ip).or_default(),
+            Client::Key(id) => self.key.entry(id.clone()).or_default(),
+        }
+    }
+}
+
+/// Token buckets per IP and per API key, plus daily quotas
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<BucketId, Bucket>>,
+    quotas: Mutex<QuotaUsage>,
+}
+
+impl RateLimiter {
+    /// `None` when rate limiting is disabled; quota usage is restored from `quota_file`
+    pub fn new(config: &RateLimitConfig) -> Option<Arc<Self>> {
+        if !config.enabled {
+            return None;
+        }
+
+        let mut quotas = match &config.quota_file {
+            Some(path) => load_quota_usage(path),
+            None => QuotaUsage::default(),
+        };
+        quotas.roll_over(utc_day());
+
+        Some(Arc::new(Self {
+            config: config.clone(),
+            buckets: Mutex::default(),
+            quotas: Mutex::new(quotas),
+        }))
+    }
+
+    /// Periodically drop idle buckets and write quota usage to disk
+    pub fn spawn_maintenance(self: &Arc<Self>) {
+        let limiter = Arc::clone(self);
+        tokio::spawn(async move {
+            let period = Duration::from_secs(QUOTA_FLUSH_INTERVAL_SECS);
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                limiter.sweep();
+                limiter.persist();
+            }
+        });
+    }
+
+    /// Write quota usage to `quota_file` if it changed since the last write
+    pub fn persist(&self) {
+        let Some(path) = &self.config.quota_file else {
+            return;
+        };
+
+        let contents = {
+            let mut quotas = self.quotas.lock().unwrap();
+            if !quotas.dirty {
+                return;
+            }
+            quotas.dirty = false;
+            serde_json::to_vec(&*quotas).expect("quota usage serializes to JSON")
+        };
+
+        // Write to a sibling file first so a crash never leaves a truncated file behind
+        let temp = path.with_extension("tmp");
+        let written = fs::write(&temp, contents).and_then(|_| fs::rename(&temp, path));
+        if let Err(err) = written {
+            tracing::warn!(path = %path.display(), error = %err, "failed to persist quota usage");
+            self.quotas.lock().unwrap().dirty = true;
+        }
+    }
+
+    /// Forget buckets that have refilled completely; they are recreated full on demand
+    fn sweep(&self) {
+        let now = Instant::now();
+        self.buckets.lock().unwrap().retain(|_, bucket| {
+            bucket.refill(now);
+            bucket.tokens < f64::from(bucket.limit.burst)
+        });
+    }
+
+    /// Admit a request, taking a token from every bucket that applies to it
+    fn check(&self, route: &str, clients: &[Client]) -> Result<Usage, Rejection> {
+        let mut quotas = self.quotas.lock().unwrap();
+        quotas.roll_over(utc_day());
+        for client in clients {
+            if let Some(quota) = self.quota(client) {
+                if *quotas.counter(client) >= quota {
+                    return Err(Rejection::QuotaExceeded {
+                        client: client.describe(),
+                        quota,
+                        retry_after: secs_until_next_day(),
+                    });
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let ids = self.bucket_limits(route, clients);
+        for (id, limit) in &ids {
+            let bucket = buckets
+                .entry(id.clone())
+                .or_insert_with(|| Bucket::full(*limit, now));
+            bucket.refill(now);
+        }
+
+        let exhausted = ids
+            .iter()
+            .map(|(id, _)| &buckets[id])
+            .filter(|bucket| bucket.tokens < 1.0)
+            .max_by_key(|bucket| bucket.retry_after());
+        if let Some(bucket) = exhausted {
+            return Err(Rejection::RateLimited {
+                usage: bucket.usage(),
+                retry_after: bucket.retry_after(),
+            });
+        }
+
+        for (id, _) in &ids {
+            buckets.get_mut(id).expect("bucket was created above").tokens -= 1.0;
+        }
+        let closest = ids
+            .iter()
+            .map(|(id, _)| buckets[id].usage())
+            .min_by_key(|usage| usage.remaining)
+            .expect("every request has at least one bucket");
+
+        for client in clients {
+            *quotas.counter(client) += 1;
+        }
+        quotas.dirty = true;
+
+        Ok(closest)
+    }
+
+    /// Default buckets for each client, then the stricter buckets of matching route rules
+    fn bucket_limits(&self, route: &str, clients: &[Client]) -> Vec<(BucketId, BucketConfig)> {
+        let mut limits = Vec::new();
+        for client in clients {
+            let default = match client {
+                Client::Ip(_) => self.config.per_ip,
+                Client::Key(_) => self.config.per_key,
+            };
+            limits.push(((0, client.clone()), default));
+
+            for (index, rule) in self.config.routes.iter().enumerate() {
+                if !route_matches(&rule.route, route) {
+                    continue;
+                }
+                let limit = match client {
+                    Client::Ip(_) => rule.per_ip,
+                    Client::Key(_) => rule.per_key,
+                };
+                if let Some(limit) = limit {
+                    limits.push(((index + 1, client.clone()), limit));
+                }
+            }
+        }
+        limits
+    }
+
+    fn quota(&self, client: &Client) -> Option<u64> {
+        match client {
+            Client::Ip(_) => self.config.daily_quota_per_ip,
+            Client::Key(_) => self.config.daily_quota_per_key,
+        }
+    }
+}
+
+fn load_quota_usage(path: &Path) -> QuotaUsage {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return QuotaUsage::default(),
+        Err(err) => {
+            tracing::warn!(path = %path.display(), error = %err, "failed to read quota usage");
+            return QuotaUsage::default();
+        }
+    };
+
+    serde_json::from_slice(&contents).unwrap_or_else(|err| {
+        tracing::warn!(path = %path.display(), error = %err, "ignoring unreadable quota usage");
+        QuotaUsage::default()
+    })
+}
+
+fn utc_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn utc_day() -> u64 {
+    utc_now_secs() / SECS_PER_DAY
+}
+
+fn secs_until_next_day() -> u64 {
+    SECS_PER_DAY - utc_now_secs() % SECS_PER_DAY
+}
+
+/// Address the request came from: the `X-Forwarded-For` entry added by the outermost of
+/// `trusted_proxies` proxies, otherwise the peer address. Entries left of it are set by
+/// the client, so they are never used.
+fn client_ip(req: &Request, trusted_proxies: usize) -> Option<IpAddr> {
+    let forwarded = (trusted_proxies > 0)
+        .then(|| forwarded_for(req.headers()))
+        .and_then(|entries| {
+            let index = entries.len().checked_sub(trusted_proxies)?;
+            entries[index].parse().ok()
+        });
+
+    forwarded.or_else(|| {
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    })
+}
+
+/// Every `X-Forwarded-For` entry in order, across repeated headers
+fn forwarded_for(headers: &HeaderMap) -> Vec<&str> {
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect()
+}
+
+/// Enforce rate limits and quotas, reporting the remaining allowance in `X-RateLimit-*`
+pub async fn rate_limit(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let Some(limiter) = &state.rate_limiter else {
+        return next.run(req).await;
+    };
+
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or_else(|| req.uri().path())
+        .to_string();
+    let ip = client_ip(&req, limiter.config.trusted_proxies).map(Client::Ip);
+    let key = req
+        .extensions()
+        .get::<AuthenticatedKey>()
+        .map(|key| Client::Key(key.id.clone()));
+    let clients = ip.into_iter().chain(key).collect::<Vec<_>>();
+    if clients.is_empty() {
+        return next.run(req).await;
+    }
+
+    match limiter.check(&route, &clients) {
+        Ok(usage) => {
+            let mut response = next.run(req).await;
+            usage.apply(response.headers_mut());
+            response
+        }
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::StatusCode, Router};
+    use serde_json::json;
+    use crate::{
+        config::Config,
+        routes,
+        testing::{self, TestResponse},
+    };
+    use super::*;
+
+    fn request(forwarded_for: &[&str]) -> Request {
+        let mut req = Request::new(Body::empty());
+        for value in forwarded_for {
+            req.headers_mut().append("x-forwarded-for", value.parse().unwrap());
+        }
+        req.extensions_mut().insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+        req
+    }
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_without_trusted_proxies() {
+        assert_eq!(client_ip(&request(&["1.1.1.1"]), 0), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn client_supplied_entries_are_skipped() {
+        // The client sent "6.6.6.6"; the proxy appended the address it saw
+        let req = request(&["6.6.6.6, 2.2.2.2"]);
+        assert_eq!(client_ip(&req, 1), ip("2.2.2.2"));
+
+        let req = request(&["6.6.6.6, 2.2.2.2", "3.3.3.3"]);
+        assert_eq!(client_ip(&req, 1), ip("3.3.3.3"));
+        assert_eq!(client_ip(&req, 2), ip("2.2.2.2"));
+    }
+
+    #[test]
+    fn short_or_invalid_headers_fall_back_to_the_peer() {
+        assert_eq!(client_ip(&request(&["2.2.2.2"]), 2), ip("10.0.0.1"));
+        assert_eq!(client_ip(&request(&[]), 1), ip("10.0.0.1"));
+        assert_eq!(client_ip(&request(&["6.6.6.6, unknown"]), 1), ip("10.0.0.1"));
+    }
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.rate_limit.enabled = true;
+        config.rate_limit.routes.clear();
+        config.rate_limit.per_ip = BucketConfig {
+            burst: 2,
+            per_minute: 60,
+        };
+        config
+    }
+
+    async fn rent(app: &Router) -> TestResponse {
+        testing::call(app, testing::post("/calc/rent", json!({ "data_len": 0 }))).await
+    }
+
+    fn quota_config(name: &str, quota: u64) -> Config {
+        let mut config = config();
+        config.rate_limit.per_ip.burst = 100;
+        config.rate_limit.daily_quota_per_ip = Some(quota);
+        let path = std::env::temp_dir().join(format!("{}-{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        config.rate_limit.quota_file = Some(path);
+        config
+    }
+
+    #[tokio::test]
+    async fn exhausted_buckets_answer_429_with_retry_after() {
+        let app = testing::app(config());
+
+        for remaining in ["1", "0"] {
+            let response = rent(&app).await;
+            assert_eq!(response.status, StatusCode::OK);
+            assert_eq!(response.header("x-ratelimit-limit"), Some("2"));
+            assert_eq!(response.header("x-ratelimit-remaining"), Some(remaining));
+            assert!(response.header("x-ratelimit-reset").is_some());
+        }
+
+        let response = rent(&app).await;
+        assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.body["code"], "RATE_LIMITED");
+        assert_eq!(response.header("retry-after"), Some("1"));
+        assert_eq!(response.header("x-ratelimit-remaining"), Some("0"));
+    }
+
+    #[tokio::test]
+    async fn quota_usage_survives_a_restart() {
+        let config = quota_config("quota-restart", 2);
+        let state = AppState::new(config.clone(), None);
+        let app = routes::app(&state);
+        assert_eq!(rent(&app).await.status, StatusCode::OK);
+        assert_eq!(rent(&app).await.status, StatusCode::OK);
+
+        let response = rent(&app).await;
+        assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.body["code"], "QUOTA_EXCEEDED");
+        let retry_after: u64 = response.header("retry-after").unwrap().parse().unwrap();
+        assert!((1..=SECS_PER_DAY).contains(&retry_after));
+
+        state.rate_limiter.as_ref().unwrap().persist();
+        let restarted = testing::app(config.clone());
+        assert_eq!(rent(&restarted).await.status, StatusCode::TOO_MANY_REQUESTS);
+        fs::remove_file(config.rate_limit.quota_file.unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn quota_usage_from_an_earlier_day_is_dropped() {
+        let config = quota_config("quota-rollover", 1);
+        let path = config.rate_limit.quota_file.clone().unwrap();
+        let peer = IpAddr::from(testing::PEER.0).to_string();
+        let usage = json!({ "day": utc_day() - 1, "ip": { peer: 50 }, "key": {} });
+        fs::write(&path, usage.to_string()).unwrap();
+
+        let app = testing::app(config);
+        assert_eq!(rent(&app).await.status, StatusCode::OK);
+        assert_eq!(rent(&app).await.status, StatusCode::TOO_MANY_REQUESTS);
+        fs::remove_file(path).unwrap();
+    }
+}
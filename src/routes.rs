@@ -1,33 +1,234 @@
-use axum::{routing::{get, post}, Router};
-use crate::handlers;
+use axum::{
+    extract::DefaultBodyLimit,
+    http::{header::RETRY_AFTER, HeaderName, Method},
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use tower::ServiceBuilder;
+use tower_http::{
+    catch_panic::CatchPanicLayer,
+    cors::{AllowHeaders, AllowOrigin, Any, CorsLayer},
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+use crate::{
+    auth,
+    config::{Config, ServerConfig},
+    handlers, limits,
+    openapi::ApiDoc,
+    ratelimit,
+    state::AppState,
+    telemetry, tls,
+};
+
+/// The application served on the API port, with its fallbacks and middleware; it is
+/// also handed to the state's dispatcher so `/rpc` and `/batch` can call into it
+pub fn app(state: &AppState) -> Router {
+    with_middleware(api_routes(&state.config), state)
+}
+
+/// `routes` behind the fallbacks and middleware of the API port
+pub fn with_middleware(routes: Router<AppState>, state: &AppState) -> Router {
+    let app = routes
+        .fallback(handlers::fallback::not_found)
+        .method_not_allowed_fallback(handlers::fallback::method_not_allowed)
+        .with_state(state.clone())
+        // Body limits are applied per route by `limits::enforce_limits`
+        .layer(DefaultBodyLimit::disable())
+        .layer(
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(telemetry::make_span)
+                        .on_response(telemetry::on_response),
+                )
+                .layer(PropagateRequestIdLayer::x_request_id())
+                .layer(middleware::from_fn(telemetry::scope_request_id))
+                .layer(middleware::from_fn(telemetry::track_metrics))
+                .layer(middleware::from_fn_with_state(state.clone(), tls::require_client_cert))
+                .layer(middleware::from_fn_with_state(state.clone(), auth::authenticate))
+                .layer(middleware::from_fn_with_state(state.clone(), ratelimit::rate_limit))
+                .layer(middleware::from_fn_with_state(state.clone(), limits::enforce_limits))
+                .layer(CatchPanicLayer::custom(limits::panic_response)),
+        )
+        .layer(cors(&state.config.server));
+    state.dispatcher.install(app.clone());
+    app
+}
+
+/// Every route served on the API port for this configuration
+pub fn api_routes(config: &Config) -> Router<AppState> {
+    let mut routes = Router::new()
+        .merge(health_routes())
+        .merge(keypair_routes())
+        .merge(token_routes())
+        .merge(message_routes())
+        .merge(send_routes())
+        .merge(instruction_routes())
+        .merge(ethereum_routes())
+        .merge(cluster_routes())
+        .merge(account_routes())
+        .merge(transaction_routes())
+        .merge(decode_routes())
+        .merge(calc_routes())
+        .merge(jsonrpc_routes())
+        .merge(batch_routes());
+    if config.features.local_simulation {
+        routes = routes.merge(simulate_routes());
+    }
+    if config.features.docs {
+        routes = routes.merge(docs_routes());
+    }
+    if config.metrics.enabled && config.metrics.port.is_none() {
+        routes = routes.merge(metrics_routes());
+    }
+    routes
+}
+
+fn cors(config: &ServerConfig) -> CorsLayer {
+    let origins = &config.cors_origins;
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::from(Any)
+    } else {
+        AllowOrigin::list(origins.iter().map(|origin| origin.parse().unwrap()))
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST])
+        // A wildcard would not cover Authorization, which API keys are sent in
+        .allow_headers(AllowHeaders::mirror_request())
+        .expose_headers([
+            HeaderName::from_static("x-request-id"),
+            ratelimit::RATE_LIMIT_LIMIT,
+            ratelimit::RATE_LIMIT_REMAINING,
+            ratelimit::RATE_LIMIT_RESET,
+            RETRY_AFTER,
+        ])
+}
 
 /// Health check routes
-pub fn health_routes() -> Router {
-    Router::new().route("/health", get(handlers::health::health_check))
+pub fn health_routes() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(handlers::health::health_check))
+        .route("/health/live", get(handlers::health::liveness))
+        .route("/health/ready", get(handlers::health::readiness))
 }
 
 /// Keypair management routes
-pub fn keypair_routes() -> Router {
-    Router::new().route("/keypair", post(handlers::keypair::generate_keypair))
+pub fn keypair_routes() -> Router<AppState> {
+    Router::new()
+        .route("/keypair", post(handlers::keypair::generate_keypair))
+        .route(
+            "/keypair/secp256k1",
+            post(handlers::keypair::generate_secp256k1_keypair),
+        )
 }
 
 /// Token-related routes
-pub fn token_routes() -> Router {
+pub fn token_routes() -> Router<AppState> {
     Router::new()
         .route("/token/create", post(handlers::token::create_token))
         .route("/token/mint", post(handlers::token::mint_token))
 }
 
 /// Message signing routes
-pub fn message_routes() -> Router {
+pub fn message_routes() -> Router<AppState> {
     Router::new()
         .route("/message/sign", post(handlers::message::sign_message))
         .route("/message/verify", post(handlers::message::verify_message))
+        .route("/message/verify/batch", post(handlers::message::verify_message_batch))
 }
 
 /// Send transaction routes
-pub fn send_routes() -> Router {
+pub fn send_routes() -> Router<AppState> {
     Router::new()
         .route("/send/sol", post(handlers::send::send_sol))
         .route("/send/token", post(handlers::send::send_token))
-}
\ No newline at end of file
+}
+
+/// Precompile instruction routes
+pub fn instruction_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/instruction/ed25519",
+            post(handlers::instruction::build_ed25519_instruction),
+        )
+        .route(
+            "/instruction/secp256k1",
+            post(handlers::instruction::build_secp256k1_instruction),
+        )
+}
+
+/// Ethereum interoperability routes
+pub fn ethereum_routes() -> Router<AppState> {
+    Router::new().route("/ethereum/address", post(handlers::ethereum::derive_eth_address))
+}
+
+/// Cluster read routes backed by the RPC client
+pub fn cluster_routes() -> Router<AppState> {
+    Router::new().route("/cluster/info", get(handlers::cluster::get_cluster_info))
+}
+
+/// Account read routes backed by the RPC client
+pub fn account_routes() -> Router<AppState> {
+    Router::new()
+        .route("/account/:pubkey", get(handlers::account_info::get_account))
+        .route(
+            "/account/:pubkey/tokens",
+            get(handlers::account_info::get_token_holdings),
+        )
+}
+
+/// Transaction building, submission and simulation routes backed by the RPC client
+pub fn transaction_routes() -> Router<AppState> {
+    Router::new()
+        .route("/transaction/build", post(handlers::transaction::build_transaction))
+        .route("/transaction/send", post(handlers::transaction::send_transaction))
+        .route(
+            "/transaction/simulate",
+            post(handlers::transaction::simulate_transaction),
+        )
+}
+
+/// Offline simulation routes
+pub fn simulate_routes() -> Router<AppState> {
+    Router::new().route("/simulate/local", post(handlers::simulate::simulate_local))
+}
+
+/// Offline account data decoding routes
+pub fn decode_routes() -> Router<AppState> {
+    Router::new().route("/decode/account", post(handlers::decode::decode_account))
+}
+
+/// Offline rent and fee calculator routes
+pub fn calc_routes() -> Router<AppState> {
+    Router::new()
+        .route("/calc/rent", post(handlers::calc::calc_rent))
+        .route("/calc/fee", post(handlers::calc::calc_fee))
+}
+
+/// JSON-RPC 2.0 facade over the routes above
+pub fn jsonrpc_routes() -> Router<AppState> {
+    Router::new().route("/rpc", post(handlers::jsonrpc::handle_rpc))
+}
+
+/// Many operations in one request
+pub fn batch_routes() -> Router<AppState> {
+    Router::new().route("/batch", post(handlers::batch::run_batch))
+}
+
+/// Prometheus metrics, served on the API port or a separate admin port
+pub fn metrics_routes() -> Router<AppState> {
+    Router::new().route("/metrics", get(handlers::metrics::render_metrics))
+}
+
+/// OpenAPI document and Swagger UI
+pub fn docs_routes() -> Router<AppState> {
+    SwaggerUi::new("/docs")
+        .url("/openapi.json", ApiDoc::openapi())
+        .into()
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use base64::Engine;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use utoipa::ToSchema;
+use crate::{
+    config::{ClusterUrls, Config, BLOCKHASH_CACHE_TTL_MS},
+    decode::mint_decimals,
+    error::{AppError, ErrorCode},
+    telemetry,
+};
+
+/// Well-known clusters a request can target instead of the configured RPC URL
+///
+/// `url` gives the public endpoint, which `rpc.clusters` in the configuration can override.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+    Testnet,
+    Localnet,
+    Custom(String),
+}
+
+impl Cluster {
+    pub fn url(&self) -> &str {
+        match self {
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Localnet => "http://127.0.0.1:8899",
+            Cluster::Custom(url) => url,
+        }
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "mainnet" | "mainnet-beta" => Ok(Cluster::Mainnet),
+            "devnet" => Ok(Cluster::Devnet),
+            "testnet" => Ok(Cluster::Testnet),
+            "localnet" | "localhost" => Ok(Cluster::Localnet),
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(Cluster::Custom(url.to_string()))
+            }
+            other => Err(AppError::invalid(
+                ErrorCode::InvalidCluster,
+                "cluster",
+                format!(
+                    "Invalid cluster: {} (expected mainnet, devnet, testnet, localnet or an \
+                     http(s) URL)",
+                    other
+                ),
+            )),
+        }
+    }
+}
+
+/// Commitment levels a cluster reports for a slot or transaction, in increasing order
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+    ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    #[default]
+    Confirmed,
+    Finalized,
+}
+
+/// Slot context attached to many RPC results
+#[derive(Deserialize)]
+pub struct RpcContext {
+    pub slot: u64,
+}
+
+/// An RPC result wrapped with the slot it was read at
+#[derive(Deserialize)]
+pub struct RpcResponse<T> {
+    pub context: RpcContext,
+    pub value: T,
+}
+
+/// Account returned by RPC methods called with base64 encoding
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccount {
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub space: Option<u64>,
+    pub data: (String, String),
+}
+
+/// A recent blockhash and the last block height at which it is valid
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestBlockhash {
+    pub blockhash: String,
+    pub last_valid_block_height: u64,
+}
+
+/// Latest blockhashes per endpoint URL and commitment
+///
+/// Expired entries are dropped whenever one is stored, so URLs passed as `cluster`
+/// do not accumulate.
+#[derive(Clone, Default)]
+struct BlockhashCache(Arc<Mutex<HashMap<BlockhashKey, (Instant, LatestBlockhash)>>>);
+
+type BlockhashKey = (String, Commitment);
+
+impl BlockhashCache {
+    fn get(&self, key: &BlockhashKey) -> Option<LatestBlockhash> {
+        let entries = self.0.lock().unwrap();
+        let (fetched_at, blockhash) = entries.get(key)?;
+        (fetched_at.elapsed() < blockhash_ttl()).then(|| blockhash.clone())
+    }
+
+    fn insert(&self, key: BlockhashKey, blockhash: LatestBlockhash, fetched_at: Instant) {
+        let mut entries = self.0.lock().unwrap();
+        entries.retain(|_, (fetched_at, _)| fetched_at.elapsed() < blockhash_ttl());
+        entries.insert(key, (fetched_at, blockhash));
+    }
+}
+
+fn blockhash_ttl() -> Duration {
+    Duration::from_millis(BLOCKHASH_CACHE_TTL_MS)
+}
+
+/// JSON-RPC client shared by all RPC-backed handlers
+#[derive(Clone)]
+pub struct RpcClient {
+    http: reqwest::Client,
+    default_url: Option<String>,
+    clusters: Arc<ClusterUrls>,
+    allow_custom_urls: bool,
+    blockhashes: BlockhashCache,
+}
+
+impl RpcClient {
+    pub fn new(config: &Config) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.rpc.timeout_secs))
+            .build()
+            .expect("failed to build HTTP client");
+
+        Self {
+            http,
+            default_url: config.rpc.url.clone(),
+            clusters: Arc::new(config.rpc.clusters.clone()),
+            allow_custom_urls: config.features.custom_cluster_urls,
+            blockhashes: BlockhashCache::default(),
+        }
+    }
+
+    /// Resolve the endpoint for a request, honouring an optional cluster override
+    pub fn endpoint(&self, cluster: Option<&str>) -> Result<RpcEndpoint, AppError> {
+        self.optional_endpoint(cluster)?.ok_or_else(|| {
+            AppError::request(
+                ErrorCode::RpcNotConfigured,
+                "No RPC endpoint configured: set SOLANA_RPC_URL or pass a cluster",
+            )
+        })
+    }
+
+    /// Like `endpoint`, but yields `None` when no RPC is configured or requested
+    pub fn optional_endpoint(&self, cluster: Option<&str>) -> Result<Option<RpcEndpoint>, AppError> {
+        let url = match cluster {
+            Some(cluster) => match cluster.parse::<Cluster>()? {
+                Cluster::Mainnet => self.clusters.mainnet.clone(),
+                Cluster::Devnet => self.clusters.devnet.clone(),
+                Cluster::Testnet => self.clusters.testnet.clone(),
+                Cluster::Localnet => self.clusters.localnet.clone(),
+                Cluster::Custom(url) if self.allow_custom_urls => url,
+                Cluster::Custom(_) => {
+                    return Err(AppError::invalid(
+                        ErrorCode::InvalidCluster,
+                        "cluster",
+                        "Custom cluster URLs are disabled; use mainnet, devnet, testnet or \
+                         localnet",
+                    ))
+                }
+            },
+            None => match &self.default_url {
+                Some(url) => url.clone(),
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(RpcEndpoint {
+            http: self.http.clone(),
+            url,
+            blockhashes: self.blockhashes.clone(),
+        }))
+    }
+}
+
+/// A single JSON-RPC endpoint resolved for one request
+#[derive(Clone)]
+pub struct RpcEndpoint {
+    http: reqwest::Client,
+    url: String,
+    blockhashes: BlockhashCache,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse {
+    result: Option<Value>,
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+impl RpcEndpoint {
+    /// Call a JSON-RPC method and deserialize its `result`
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, AppError> {
+        let started = Instant::now();
+        let result = self.request(method, params).await;
+        telemetry::record_rpc_call(method, started.elapsed(), result.is_ok());
+        result
+    }
+
+    async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, AppError> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response = self
+            .http
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| AppError::Rpc(format!("{} request failed: {}", method, e.without_url())))?;
+
+        let status = response.status();
+        let response: JsonRpcResponse = response.json().await.map_err(|e| {
+            AppError::Rpc(format!("{} returned an invalid response ({}): {}", method, status, e))
+        })?;
+
+        if let Some(error) = response.error {
+            return Err(AppError::Rpc(format!(
+                "{} failed ({}): {}",
+                method, error.code, error.message
+            )));
+        }
+
+        serde_json::from_value(response.result.unwrap_or(Value::Null))
+            .map_err(|e| AppError::Rpc(format!("{} returned an unexpected result: {}", method, e)))
+    }
+
+    /// Fetch a mint account and read its decimals
+    pub async fn mint_decimals(&self, mint: &Pubkey) -> Result<u8, AppError> {
+        let response: RpcResponse<Option<RpcAccount>> = self
+            .call(
+                "getAccountInfo",
+                json!([mint.to_string(), { "encoding": "base64" }]),
+            )
+            .await?;
+
+        let account = response
+            .value
+            .ok_or_else(|| AppError::NotFound(format!("Mint {} not found", mint)))?;
+
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(&account.data.0)
+            .map_err(|_| AppError::Rpc("getAccountInfo returned invalid base64 data".to_string()))?;
+        let owner = account
+            .owner
+            .parse::<Pubkey>()
+            .map_err(|_| AppError::Rpc("getAccountInfo returned an invalid owner".to_string()))?;
+
+        mint_decimals(&owner, &data).ok_or_else(|| {
+            AppError::invalid(
+                ErrorCode::InvalidValue,
+                "mint",
+                format!("Account {} is not a token mint", mint),
+            )
+        })
+    }
+
+    /// Fetch the latest blockhash, reusing a recently fetched one for this endpoint
+    pub async fn latest_blockhash(
+        &self,
+        commitment: Commitment,
+    ) -> Result<LatestBlockhash, AppError> {
+        let key = (self.url.clone(), commitment);
+        if let Some(cached) = self.blockhashes.get(&key) {
+            return Ok(cached);
+        }
+
+        let latest: RpcResponse<LatestBlockhash> = self
+            .call("getLatestBlockhash", json!([{ "commitment": commitment }]))
+            .await?;
+
+        self.blockhashes.insert(key, latest.value.clone(), Instant::now());
+
+        Ok(latest.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blockhash(value: &str) -> LatestBlockhash {
+        LatestBlockhash {
+            blockhash: value.to_string(),
+            last_valid_block_height: 1,
+        }
+    }
+
+    #[test]
+    fn blockhash_cache_drops_expired_endpoints() {
+        let cache = BlockhashCache::default();
+        let expired = Instant::now() - blockhash_ttl() - Duration::from_millis(1);
+        for index in 0..100 {
+            let url = format!("http://10.0.0.{}", index);
+            cache.insert((url, Commitment::Confirmed), blockhash("old"), expired);
+        }
+        let key = ("http://127.0.0.1:8899".to_string(), Commitment::Confirmed);
+        assert!(cache.get(&("http://10.0.0.1".to_string(), Commitment::Confirmed)).is_none());
+
+        cache.insert(key.clone(), blockhash("new"), Instant::now());
+        assert_eq!(cache.0.lock().unwrap().len(), 1);
+        assert_eq!(cache.get(&key).unwrap().blockhash, "new");
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    fmt, fs,
+    future::Future,
+    io,
+    net::SocketAddr,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use axum::{extract::ConnectInfo, Router};
+use hyper::{body::Incoming, server::conn::http1, service::service_fn, Request};
+use hyper_util::{
+    rt::TokioIo,
+    server::graceful::{GracefulShutdown, Watcher},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream, UnixListener},
+};
+use tokio_rustls::TlsAcceptor;
+use tower::Service;
+use crate::{
+    config::{ServerConfig, TLS_HANDSHAKE_TIMEOUT_SECS},
+    tls::{ClientCertificate, TlsReloader},
+};
+
+/// Where the API is served
+pub enum Listener {
+    Tcp(TcpListener),
+    Tls(TcpListener, TlsReloader),
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    pub async fn bind(config: &ServerConfig, tls: Option<TlsReloader>) -> io::Result<Self> {
+        if let Some(path) = &config.unix_socket {
+            let listener = bind_unix(path, config.unix_socket_mode)?;
+            return Ok(Listener::Unix(listener, path.clone()));
+        }
+
+        let listener = TcpListener::bind(SocketAddr::new(config.bind_address, config.port)).await?;
+        Ok(match tls {
+            Some(tls) => Listener::Tls(listener, tls),
+            None => Listener::Tcp(listener),
+        })
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let local_addr = |listener: &TcpListener| listener.local_addr().map_err(|_| fmt::Error);
+        match self {
+            Listener::Tcp(listener) => write!(f, "http://{}", local_addr(listener)?),
+            Listener::Tls(listener, _) => write!(f, "https://{}", local_addr(listener)?),
+            Listener::Unix(_, path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Bind `path` and give it `mode`, rather than whatever the umask left. A socket left behind
+/// by a previous run would make the bind fail, so it is removed first.
+fn bind_unix(path: &Path, mode: u32) -> io::Result<UnixListener> {
+    if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(listener)
+}
+
+/// Accept connections until `shutdown` resolves, then wait up to `drain_timeout` for
+/// in-flight requests to finish
+pub async fn serve(
+    listener: Listener,
+    app: Router,
+    shutdown: impl Future<Output = ()>,
+    drain_timeout: Duration,
+) {
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        let watcher = graceful.watcher();
+        match &listener {
+            Listener::Tcp(listener) => {
+                let accepted = tokio::select! {
+                    accepted = listener.accept() => accepted,
+                    _ = &mut shutdown => break,
+                };
+                let Some((stream, peer)) = accept(accepted).await else {
+                    continue;
+                };
+                tokio::spawn(serve_connection(stream, app.clone(), watcher, Some(peer), None));
+            }
+            Listener::Tls(listener, tls) => {
+                let accepted = tokio::select! {
+                    accepted = listener.accept() => accepted,
+                    _ = &mut shutdown => break,
+                };
+                let Some((stream, peer)) = accept(accepted).await else {
+                    continue;
+                };
+                let acceptor = tls.acceptor();
+                tokio::spawn(serve_tls_connection(stream, acceptor, app.clone(), watcher, peer));
+            }
+            Listener::Unix(listener, _) => {
+                let accepted = tokio::select! {
+                    accepted = listener.accept() => accepted,
+                    _ = &mut shutdown => break,
+                };
+                let Some((stream, _)) = accept(accepted).await else {
+                    continue;
+                };
+                tokio::spawn(serve_connection(stream, app.clone(), watcher, None, None));
+            }
+        }
+    }
+
+    if let Listener::Unix(_, path) = &listener {
+        if let Err(err) = fs::remove_file(path) {
+            tracing::warn!(path = %path.display(), error = %err, "failed to remove socket");
+        }
+    }
+    drop(listener);
+
+    tracing::info!(connections = graceful.count(), "Draining connections");
+    if tokio::time::timeout(drain_timeout, graceful.shutdown()).await.is_err() {
+        tracing::warn!("Shutdown timeout elapsed; closing remaining connections");
+    }
+}
+
+/// Resolves on SIGINT or, on Unix, SIGTERM
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c().await.expect("failed to listen for SIGINT");
+    };
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    tokio::select! {
+        _ = interrupt => tracing::info!("Received SIGINT"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+/// Errors concerning a single connection drop it; others, such as running out of
+/// file descriptors, pause accepting briefly
+async fn accept<T>(result: io::Result<T>) -> Option<T> {
+    match result {
+        Ok(accepted) => Some(accepted),
+        Err(err) => {
+            if !matches!(
+                err.kind(),
+                io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::ConnectionReset
+            ) {
+                tracing::error!(error = %err, "failed to accept connection");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            None
+        }
+    }
+}
+
+async fn serve_tls_connection(
+    stream: TcpStream,
+    acceptor: TlsAcceptor,
+    app: Router,
+    watcher: Watcher,
+    peer: SocketAddr,
+) {
+    let timeout = Duration::from_secs(TLS_HANDSHAKE_TIMEOUT_SECS);
+    let stream = match tokio::time::timeout(timeout, acceptor.accept(stream)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(err)) => {
+            tracing::debug!(%peer, error = %err, "TLS handshake failed");
+            return;
+        }
+        Err(_) => {
+            tracing::debug!(%peer, "TLS handshake timed out");
+            return;
+        }
+    };
+
+    let cert = ClientCertificate::from_stream(&stream);
+    serve_connection(stream, app, watcher, Some(peer), cert).await;
+}
+
+async fn serve_connection<IO>(
+    io: IO,
+    app: Router,
+    watcher: Watcher,
+    peer: Option<SocketAddr>,
+    cert: Option<ClientCertificate>,
+) where
+    IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = service_fn(move |mut req: Request<Incoming>| {
+        // Peer addresses identify clients for per-IP rate limits
+        if let Some(peer) = peer {
+            req.extensions_mut().insert(ConnectInfo(peer));
+        }
+        if let Some(cert) = &cert {
+            req.extensions_mut().insert(cert.clone());
+        }
+        // A router is always ready, so it can be called without polling first
+        app.clone().call(req)
+    });
+
+    let connection = http1::Builder::new().serve_connection(TokioIo::new(io), service);
+    if let Err(err) = watcher.watch(connection).await {
+        tracing::debug!(error = %err, "connection closed with an error");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn unix_sockets_get_the_configured_mode() {
+        let path = std::env::temp_dir().join(format!("api-{}.sock", std::process::id()));
+        for mode in [0o600, 0o660] {
+            let _listener = bind_unix(&path, mode).unwrap();
+            let permissions = fs::metadata(&path).unwrap().permissions();
+            assert_eq!(permissions.mode() & 0o777, mode);
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
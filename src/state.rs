@@ -0,0 +1,64 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use crate::{
+    auth::ApiKeys, config::Config, dispatch::Dispatcher, limits::RequestLimits,
+    ratelimit::RateLimiter, rpc::RpcClient,
+};
+
+/// Shared application state handed to every router
+#[derive(Clone)]
+pub struct AppState {
+    pub rpc: RpcClient,
+    pub config: Arc<Config>,
+    /// Present when metrics are enabled
+    pub metrics: Option<PrometheusHandle>,
+    /// Present when API key authentication is enabled
+    pub api_keys: Option<ApiKeys>,
+    /// Present when rate limiting is enabled
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    pub limits: Arc<RequestLimits>,
+    pub lifecycle: Arc<Lifecycle>,
+    /// Serves `/rpc` and `/batch` calls through the application's own routes
+    pub dispatcher: Dispatcher,
+}
+
+/// Process start time and shutdown progress, reported by the health routes
+pub struct Lifecycle {
+    pub started: Instant,
+    draining: AtomicBool,
+}
+
+impl Lifecycle {
+    /// Readiness fails from now on so load balancers stop sending traffic
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+}
+
+impl AppState {
+    pub fn new(config: Config, metrics: Option<PrometheusHandle>) -> Self {
+        Self {
+            rpc: RpcClient::new(&config),
+            api_keys: ApiKeys::new(&config.auth),
+            rate_limiter: RateLimiter::new(&config.rate_limit),
+            limits: Arc::new(RequestLimits::new(&config)),
+            lifecycle: Arc::new(Lifecycle {
+                started: Instant::now(),
+                draining: AtomicBool::new(false),
+            }),
+            dispatcher: Dispatcher::default(),
+            config: Arc::new(config),
+            metrics,
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    io::IsTerminal,
+    time::{Duration, Instant},
+};
+use axum::{
+    extract::{MatchedPath, Request},
+    http::Response,
+    middleware::Next,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tower_http::request_id::RequestId;
+use tracing::{field::Empty, Span};
+use tracing_subscriber::EnvFilter;
+use crate::{
+    config::{LogFormat, LoggingConfig, LATENCY_BUCKETS_SECS, METRICS_UPKEEP_INTERVAL_SECS},
+    error::ErrorCode,
+};
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Install the global subscriber; the filter was validated with the rest of the configuration
+pub fn init(config: &LoggingConfig) {
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(&config.filter))
+        .with_ansi(std::io::stdout().is_terminal());
+
+    match config.format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Pretty => subscriber.pretty().init(),
+        LogFormat::Json => subscriber.json().with_current_span(true).with_span_list(false).init(),
+    }
+}
+
+/// Install the Prometheus recorder behind the `metrics` macros
+pub fn init_metrics() -> PrometheusHandle {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Suffix("_duration_seconds".to_string()),
+            LATENCY_BUCKETS_SECS,
+        )
+        .expect("latency buckets are not empty")
+        .install_recorder()
+        .expect("metrics recorder is installed once");
+
+    // Without the exporter's own HTTP listener, histograms are only compacted on upkeep
+    let upkeep = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(METRICS_UPKEEP_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            upkeep.run_upkeep();
+        }
+    });
+
+    handle
+}
+
+/// One span per request; status and latency are filled in once the response is ready
+pub fn make_span(req: &Request) -> Span {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or_else(|| req.uri().path());
+
+    tracing::info_span!(
+        "request",
+        method = %req.method(),
+        route,
+        request_id = request_id(req).unwrap_or_default(),
+        api_key = Empty,
+        client_cert = Empty,
+        status = Empty,
+        latency_ms = Empty,
+    )
+}
+
+pub fn on_response<B>(response: &Response<B>, latency: Duration, span: &Span) {
+    span.record("status", response.status().as_u16());
+    span.record("latency_ms", u64::try_from(latency.as_millis()).unwrap_or(u64::MAX));
+    tracing::info!("request completed");
+}
+
+/// Expose the request ID to `current_request_id` while the request is handled
+pub async fn scope_request_id(req: Request, next: Next) -> axum::response::Response {
+    let id = request_id(&req).unwrap_or_default().to_string();
+    REQUEST_ID.scope(id, next.run(req)).await
+}
+
+/// ID of the request being handled on this task, if any
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID
+        .try_with(|id| id.clone())
+        .ok()
+        .filter(|id| !id.is_empty())
+}
+
+fn request_id(req: &Request) -> Option<&str> {
+    req.extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+}
+
+/// Request count, latency and error count per route
+pub async fn track_metrics(req: Request, next: Next) -> axum::response::Response {
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let started = Instant::now();
+
+    let response = next.run(req).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::counter!(
+        "http_requests_total",
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => status,
+    )
+    .increment(1);
+    metrics::histogram!(
+        "http_request_duration_seconds",
+        "method" => method.clone(),
+        "route" => route.clone(),
+    )
+    .record(started.elapsed().as_secs_f64());
+
+    if let Some(code) = response.extensions().get::<ErrorCode>() {
+        let code = serde_json::to_value(code)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default();
+        metrics::counter!(
+            "http_request_errors_total",
+            "method" => method,
+            "route" => route,
+            "code" => code,
+        )
+        .increment(1);
+    }
+
+    response
+}
+
+pub fn record_keypair_generated(curve: &'static str) {
+    metrics::counter!("keypairs_generated_total", "curve" => curve).increment(1);
+}
+
+pub fn record_message_signed() {
+    metrics::counter!("messages_signed_total").increment(1);
+}
+
+pub fn record_message_verified(valid: bool) {
+    let result = if valid { "valid" } else { "invalid" };
+    metrics::counter!("messages_verified_total", "result" => result).increment(1);
+}
+
+pub fn record_instruction_built(program_id: &str) {
+    metrics::counter!("instructions_built_total", "program_id" => program_id.to_string())
+        .increment(1);
+}
+
+pub fn record_rpc_call(method: &str, latency: Duration, succeeded: bool) {
+    let outcome = if succeeded { "ok" } else { "error" };
+    metrics::histogram!(
+        "rpc_request_duration_seconds",
+        "method" => method.to_string(),
+        "outcome" => outcome,
+    )
+    .record(latency.as_secs_f64());
+}
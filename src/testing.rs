@@ -0,0 +1,62 @@
+//! Helpers for tests that drive the whole application through its middleware
+
+use std::net::SocketAddr;
+use axum::{
+    body::{to_bytes, Body},
+    extract::ConnectInfo,
+    http::{header::CONTENT_TYPE, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use tower::Service;
+use crate::{config::Config, routes, state::AppState};
+
+/// Peer address of test requests
+pub const PEER: ([u8; 4], u16) = ([192, 0, 2, 1], 40_000);
+
+/// The application as served for `config`
+pub fn app(config: Config) -> Router {
+    routes::app(&AppState::new(config, None))
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// Parsed JSON body, `Null` when empty
+    pub body: Value,
+}
+
+impl TestResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+pub async fn call(app: &Router, req: Request<Body>) -> TestResponse {
+    let response = app.clone().call(req).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    TestResponse {
+        status,
+        headers,
+        body,
+    }
+}
+
+pub fn get(uri: &str) -> Request<Body> {
+    request(Method::GET, uri, Body::empty())
+}
+
+pub fn post(uri: &str, body: Value) -> Request<Body> {
+    let mut req = request(Method::POST, uri, Body::from(body.to_string()));
+    req.headers_mut().insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    req
+}
+
+fn request(method: Method, uri: &str, body: Body) -> Request<Body> {
+    let mut req = Request::builder().method(method).uri(uri).body(body).unwrap();
+    req.extensions_mut().insert(ConnectInfo(SocketAddr::from(PEER)));
+    req
+}
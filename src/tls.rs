@@ -0,0 +1,397 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio_rustls::{
+    rustls::{
+        self, server::AllowAnyAnonymousOrAuthenticatedClient, Certificate, PrivateKey,
+        RootCertStore, ServerConfig,
+    },
+    server::TlsStream,
+    TlsAcceptor,
+};
+use tracing::Span;
+use crate::{
+    auth::route_matches,
+    config::{TlsConfig, TLS_RELOAD_CHECK_INTERVAL_SECS},
+    error::{AppError, ErrorCode},
+    state::AppState,
+};
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("failed to read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    #[error("{} contains no {what}", path.display())]
+    Empty {
+        path: PathBuf,
+        what: &'static str,
+    },
+    #[error("invalid TLS configuration: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Verified client certificate presented on the connection, available as a request extension
+#[derive(Debug, Clone)]
+pub struct ClientCertificate {
+    /// Hex SHA-256 fingerprint of the leaf certificate
+    pub sha256: String,
+}
+
+impl ClientCertificate {
+    pub fn from_stream<IO>(stream: &TlsStream<IO>) -> Option<Self> {
+        let leaf = stream.get_ref().1.peer_certificates()?.first()?;
+        Some(Self {
+            sha256: hex::encode(Sha256::digest(&leaf.0)),
+        })
+    }
+}
+
+/// Server TLS settings, rebuilt when the certificate, key or client CA files change
+#[derive(Clone)]
+pub struct TlsReloader {
+    config: TlsConfig,
+    current: Arc<RwLock<Arc<ServerConfig>>>,
+}
+
+impl TlsReloader {
+    pub fn new(config: &TlsConfig) -> Result<Self, TlsError> {
+        Ok(Self {
+            current: Arc::new(RwLock::new(Arc::new(server_config(config)?))),
+            config: config.clone(),
+        })
+    }
+
+    /// Acceptor for the next connection; connections already open keep their settings
+    pub fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(Arc::clone(&self.current.read().unwrap()))
+    }
+
+    /// Poll the files for changes; a failed reload keeps serving the previous certificate
+    pub fn spawn_reload(&self) {
+        let reloader = self.clone();
+        tokio::spawn(async move {
+            let period = Duration::from_secs(TLS_RELOAD_CHECK_INTERVAL_SECS);
+            let mut interval = tokio::time::interval(period);
+            let mut last_modified = reloader.modified();
+            loop {
+                interval.tick().await;
+                let modified = reloader.modified();
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                match reloader.reload() {
+                    Ok(()) => tracing::info!("TLS certificate reloaded"),
+                    Err(err) => tracing::warn!(error = %err, "TLS reload failed"),
+                }
+            }
+        });
+    }
+
+    /// Rebuild the settings from the files, keeping the current ones if that fails
+    fn reload(&self) -> Result<(), TlsError> {
+        let config = server_config(&self.config)?;
+        *self.current.write().unwrap() = Arc::new(config);
+        Ok(())
+    }
+
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        [&self.config.cert, &self.config.key, &self.config.client_ca]
+            .into_iter()
+            .flatten()
+            .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+            .collect()
+    }
+}
+
+fn server_config(config: &TlsConfig) -> Result<ServerConfig, TlsError> {
+    let (Some(cert_path), Some(key_path)) = (&config.cert, &config.key) else {
+        unreachable!("TLS is only configured with both a certificate and a key");
+    };
+    let certs = read_certs(cert_path)?;
+    let key = read_key(key_path)?;
+
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &config.client_ca {
+        // Certificates are verified when presented; routes that need one check for it
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(ca_path)? {
+                roots.add(&cert)?;
+            }
+            builder.with_client_cert_verifier(
+                AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed(),
+            )
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server = builder.with_single_cert(certs, key)?;
+    server.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(server)
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, TlsError> {
+    File::open(path).map(BufReader::new).map_err(|source| TlsError::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn read_certs(path: &Path) -> Result<Vec<Certificate>, TlsError> {
+    let certs = rustls_pemfile::certs(&mut open(path)?).map_err(|source| TlsError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    if certs.is_empty() {
+        return Err(TlsError::Empty {
+            path: path.to_path_buf(),
+            what: "PEM certificates",
+        });
+    }
+
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn read_key(path: &Path) -> Result<PrivateKey, TlsError> {
+    let items = rustls_pemfile::read_all(&mut open(path)?).map_err(|source| TlsError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| TlsError::Empty {
+            path: path.to_path_buf(),
+            what: "PEM private key",
+        })
+}
+
+/// Reject requests to `tls.client_cert_routes` without a verified client certificate
+pub async fn require_client_cert(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let tls = &state.config.tls;
+    if tls.client_ca.is_none() {
+        return Ok(next.run(req).await);
+    }
+    let Some(route) = req.extensions().get::<MatchedPath>().map(MatchedPath::as_str) else {
+        return Ok(next.run(req).await);
+    };
+    if !tls.client_cert_routes.iter().any(|pattern| route_matches(pattern, route)) {
+        return Ok(next.run(req).await);
+    }
+
+    let cert = req.extensions().get::<ClientCertificate>().ok_or_else(|| {
+        AppError::request(
+            ErrorCode::Forbidden,
+            format!("{} requires a TLS client certificate", route),
+        )
+    })?;
+    Span::current().record("client_cert", cert.sha256.as_str());
+
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future, net::SocketAddr};
+    use rcgen::{
+        BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+    use tokio_rustls::{rustls::ClientConfig, TlsConnector};
+    use crate::{config::Config, routes, server, state::AppState};
+    use super::*;
+
+    /// A CA and the certificates it issues, written to a temporary directory
+    struct Pki {
+        dir: PathBuf,
+        ca: rcgen::Certificate,
+    }
+
+    impl Pki {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            let mut params = CertificateParams::new(Vec::new());
+            params.distinguished_name.push(DnType::CommonName, "test CA");
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = rcgen::Certificate::from_params(params).unwrap();
+            fs::write(dir.join("ca.pem"), ca.serialize_pem().unwrap()).unwrap();
+            Self { dir, ca }
+        }
+
+        fn path(&self, file: &str) -> PathBuf {
+            self.dir.join(file)
+        }
+
+        /// Issue a certificate for `localhost`, returning its DER encoding
+        fn issue(&self, name: &str, cert_file: &str, key_file: &str) -> Vec<u8> {
+            let mut params = CertificateParams::new(vec!["localhost".to_string()]);
+            params.distinguished_name.push(DnType::CommonName, name);
+            params.extended_key_usages = vec![
+                ExtendedKeyUsagePurpose::ServerAuth,
+                ExtendedKeyUsagePurpose::ClientAuth,
+            ];
+            let cert = rcgen::Certificate::from_params(params).unwrap();
+            let pem = cert.serialize_pem_with_signer(&self.ca).unwrap();
+            fs::write(self.path(cert_file), &pem).unwrap();
+            fs::write(self.path(key_file), cert.serialize_private_key_pem()).unwrap();
+            rustls_pemfile::certs(&mut pem.as_bytes()).unwrap().remove(0)
+        }
+
+        fn tls_config(&self) -> TlsConfig {
+            TlsConfig {
+                cert: Some(self.path("server.pem")),
+                key: Some(self.path("server.key")),
+                ..TlsConfig::default()
+            }
+        }
+
+        /// Client trusting this CA, presenting the client certificate issued by `issuer`
+        fn connector(&self, issuer: Option<&Pki>) -> TlsConnector {
+            let mut roots = RootCertStore::empty();
+            roots.add(&read_certs(&self.path("ca.pem")).unwrap()[0]).unwrap();
+            let builder = ClientConfig::builder()
+                .with_safe_defaults()
+                .with_root_certificates(roots);
+            let config = match issuer {
+                Some(issuer) => {
+                    let certs = read_certs(&issuer.path("client.pem")).unwrap();
+                    let key = read_key(&issuer.path("client.key")).unwrap();
+                    builder.with_client_auth_cert(certs, key).unwrap()
+                }
+                None => builder.with_no_client_auth(),
+            };
+            TlsConnector::from(Arc::new(config))
+        }
+    }
+
+    impl Drop for Pki {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    /// DER certificate the server presents in a fresh handshake
+    async fn served_certificate(reloader: &TlsReloader, connector: &TlsConnector) -> Vec<u8> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let acceptor = reloader.acceptor();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ = acceptor.accept(stream).await;
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let domain = rustls::ServerName::try_from("localhost").unwrap();
+        let stream = connector.connect(domain, stream).await.unwrap();
+        let served = stream.get_ref().1.peer_certificates().unwrap()[0].0.clone();
+        server.await.unwrap();
+        served
+    }
+
+    #[tokio::test]
+    async fn reload_swaps_the_certificate_and_keeps_the_last_good_one() {
+        let pki = Pki::new("tls-reload");
+        let first = pki.issue("first", "server.pem", "server.key");
+        let reloader = TlsReloader::new(&pki.tls_config()).unwrap();
+        let connector = pki.connector(None);
+        assert_eq!(served_certificate(&reloader, &connector).await, first);
+
+        let second = pki.issue("second", "server.pem", "server.key");
+        reloader.reload().unwrap();
+        assert_eq!(served_certificate(&reloader, &connector).await, second);
+
+        fs::write(pki.path("server.pem"), "not a certificate").unwrap();
+        assert!(reloader.reload().is_err());
+        assert_eq!(served_certificate(&reloader, &connector).await, second);
+    }
+
+    /// Status code of a request sent over a fresh TLS connection; `None` when the server
+    /// ends the connection without answering
+    async fn status(
+        addr: SocketAddr,
+        connector: &TlsConnector,
+        route: &str,
+        body: &str,
+    ) -> Option<u16> {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let domain = rustls::ServerName::try_from("localhost").unwrap();
+        let mut stream = connector.connect(domain, stream).await.ok()?;
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            route,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.ok()?;
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response).await;
+        response.get(9..12)?.parse().ok()
+    }
+
+    #[tokio::test]
+    async fn signing_routes_require_a_client_certificate() {
+        let pki = Pki::new("tls-client-cert");
+        pki.issue("server", "server.pem", "server.key");
+        pki.issue("client", "client.pem", "client.key");
+        // Issued by a CA the server does not trust
+        let stranger = Pki::new("tls-stranger");
+        stranger.issue("stranger", "client.pem", "client.key");
+
+        let config = Config {
+            tls: TlsConfig {
+                client_ca: Some(pki.path("ca.pem")),
+                ..pki.tls_config()
+            },
+            ..Config::default()
+        };
+        let reloader = TlsReloader::new(&config.tls).unwrap();
+        let app = routes::app(&AppState::new(config, None));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let listener = server::Listener::Tls(listener, reloader);
+        tokio::spawn(server::serve(listener, app, future::pending(), Duration::from_secs(1)));
+
+        let anonymous = pki.connector(None);
+        assert_eq!(status(addr, &anonymous, "/message/sign", "{}").await, Some(403));
+        let rent = r#"{"data_len": 0}"#;
+        assert_eq!(status(addr, &anonymous, "/calc/rent", rent).await, Some(200));
+
+        // Past the certificate check, the empty body is what gets rejected
+        let client = pki.connector(Some(&pki));
+        assert_eq!(status(addr, &client, "/message/sign", "{}").await, Some(400));
+
+        let untrusted = pki.connector(Some(&stranger));
+        assert_eq!(status(addr, &untrusted, "/calc/rent", rent).await, None);
+    }
+}
@@ -1,13 +1,31 @@
+use std::fmt;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use zeroize::Zeroize;
+use crate::{
+    error::{ErrorCode, FieldError},
+    rpc::Commitment,
+};
 
 /// Standard API response wrapper
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    /// Request field the error refers to, when it concerns a single field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Every failure, when several fields failed validation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
+    /// Echoes the `X-Request-Id` of a failed request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl<T> ApiResponse<T> {
@@ -16,27 +34,88 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            code: None,
+            field: None,
+            errors: None,
+            request_id: None,
         }
     }
 
-    pub fn error(message: String) -> Self {
+    pub fn error(code: ErrorCode, message: String) -> Self {
         Self {
             success: false,
             data: None,
             error: Some(message),
+            code: Some(code),
+            field: None,
+            errors: None,
+            request_id: None,
         }
     }
 }
 
+// Health types
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    /// Shutdown has begun; in-flight requests are being drained
+    Draining,
+    /// A dependency check failed
+    Unavailable,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub version: String,
+    pub git_commit: String,
+    pub uptime_secs: u64,
+    /// Optional features that are switched on, such as `tls` or `rate_limit`
+    pub features: Vec<String>,
+    /// Dependency checks; only run for readiness
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<HealthCheck>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthCheck {
+    pub name: String,
+    pub ok: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 // Keypair types
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct KeypairResponse {
     pub pubkey: String,
     pub secret: String,
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct Secp256k1KeypairResponse {
+    pub secret: String,
+    pub public_key: String,
+    pub eth_address: String,
+}
+
+// Ethereum types
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EthAddressRequest {
+    pub secret: Option<Secret>,
+    pub public_key: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EthAddressResponse {
+    pub eth_address: String,
+    pub public_key: String,
+}
+
 // Token types
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct TokenCreateRequest {
     #[serde(rename = "mintAuthority")]
     pub mint_authority: String,
@@ -44,22 +123,28 @@ pub struct TokenCreateRequest {
     pub decimals: u8,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TokenCreateResponse {
     pub program_id: String,
     pub accounts: Vec<AccountMetaResponse>,
     pub instruction_data: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct TokenMintRequest {
     pub mint: String,
     pub destination: String,
     pub authority: String,
-    pub amount: u64,
+    /// Base units; mutually exclusive with `ui_amount`
+    pub amount: Option<AmountInput>,
+    /// Decimal amount such as "12.345", scaled by `decimals`
+    pub ui_amount: Option<String>,
+    /// Mint decimals; looked up from the mint over RPC when omitted
+    pub decimals: Option<u8>,
+    pub cluster: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TokenMintResponse {
     pub program_id: String,
     pub accounts: Vec<AccountMetaResponse>,
@@ -67,42 +152,51 @@ pub struct TokenMintResponse {
 }
 
 // Message signing types
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct MessageSignRequest {
     pub message: String,
-    pub secret: String,
+    pub secret: Secret,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct MessageSignResponse {
     pub signature: String,
     pub public_key: String,
     pub message: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct MessageVerifyRequest {
     pub message: String,
     pub signature: String,
     pub pubkey: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct MessageVerifyResponse {
     pub valid: bool,
     pub message: String,
     pub pubkey: String,
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct MessageVerifyBatchResponse {
+    pub valid: Vec<bool>,
+    pub all_valid: bool,
+}
+
 // Send SOL types
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SendSolRequest {
     pub from: String,
     pub to: String,
-    pub lamports: u64,
+    /// Mutually exclusive with `sol`
+    pub lamports: Option<AmountInput>,
+    /// Decimal SOL amount such as "1.5"
+    pub sol: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SendSolResponse {
     pub program_id: String,
     pub accounts: Vec<String>,
@@ -110,32 +204,573 @@ pub struct SendSolResponse {
 }
 
 // Send token types
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SendTokenRequest {
     pub destination: String,
     pub mint: String,
     pub owner: String,
-    pub amount: u64,
+    /// Base units; mutually exclusive with `ui_amount`
+    pub amount: Option<AmountInput>,
+    /// Decimal amount such as "12.345", scaled by `decimals`
+    pub ui_amount: Option<String>,
+    /// Mint decimals; looked up from the mint over RPC when omitted
+    pub decimals: Option<u8>,
+    pub cluster: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SendTokenResponse {
     pub program_id: String,
     pub accounts: Vec<SendTokenAccountMeta>,
     pub instruction_data: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct SendTokenAccountMeta {
     pub pubkey: String,
     #[serde(rename = "isSigner")]
     pub is_signer: bool,
 }
 
+// Precompile instruction types
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct Ed25519InstructionRequest {
+    pub signatures: Vec<Ed25519SignatureEntry>,
+}
+
+/// A signature to check, either carried inline or referenced from other instructions
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct Ed25519SignatureEntry {
+    pub message: Option<String>,
+    pub signature: Option<String>,
+    pub pubkey: Option<String>,
+    pub secret: Option<Secret>,
+    pub offsets: Option<SignatureOffsets>,
+}
+
+/// Layout of one entry in the Ed25519 program offsets header
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema)]
+pub struct SignatureOffsets {
+    pub signature_offset: u16,
+    pub signature_instruction_index: u16,
+    pub public_key_offset: u16,
+    pub public_key_instruction_index: u16,
+    pub message_data_offset: u16,
+    pub message_data_size: u16,
+    pub message_instruction_index: u16,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Ed25519InstructionResponse {
+    pub program_id: String,
+    pub accounts: Vec<AccountMetaResponse>,
+    pub instruction_data: String,
+    pub offsets: Vec<SignatureOffsets>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct Secp256k1InstructionRequest {
+    pub signatures: Vec<Secp256k1SignatureEntry>,
+    /// Position of this instruction in the transaction, used for inline data offsets
+    #[serde(default)]
+    pub instruction_index: u8,
+}
+
+/// A recoverable signature to check, either carried inline or referenced from other instructions
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct Secp256k1SignatureEntry {
+    pub message: Option<String>,
+    pub signature: Option<String>,
+    pub eth_address: Option<String>,
+    pub secret: Option<Secret>,
+    pub offsets: Option<Secp256k1Offsets>,
+}
+
+/// Layout of one entry in the Secp256k1 program offsets header
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema)]
+pub struct Secp256k1Offsets {
+    pub signature_offset: u16,
+    pub signature_instruction_index: u8,
+    pub eth_address_offset: u16,
+    pub eth_address_instruction_index: u8,
+    pub message_data_offset: u16,
+    pub message_data_size: u16,
+    pub message_instruction_index: u8,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Secp256k1InstructionResponse {
+    pub program_id: String,
+    pub accounts: Vec<AccountMetaResponse>,
+    pub instruction_data: String,
+    pub offsets: Vec<Secp256k1Offsets>,
+}
+
+// Cluster types
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ClusterQuery {
+    pub cluster: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ClusterInfoResponse {
+    pub solana_core: String,
+    pub feature_set: Option<u32>,
+    pub slot: u64,
+    pub block_height: u64,
+    pub epoch: u64,
+}
+
+// Account read types
+#[derive(Serialize, ToSchema)]
+pub struct AccountInfoResponse {
+    pub pubkey: String,
+    pub slot: u64,
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub space: u64,
+    pub data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parsed: Option<ParsedAccountData>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ParsedAccountData {
+    pub program: String,
+    #[serde(rename = "type")]
+    pub account_type: String,
+    pub info: serde_json::Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TokenHoldingsResponse {
+    pub owner: String,
+    pub slot: u64,
+    pub tokens: Vec<TokenHolding>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TokenHolding {
+    pub address: String,
+    pub mint: String,
+    pub program_id: String,
+    pub amount: String,
+    pub decimals: u8,
+    pub ui_amount: Option<f64>,
+    pub ui_amount_string: String,
+    pub state: String,
+}
+
+// Transaction submission types
+#[derive(Deserialize, ToSchema)]
+pub struct SendTransactionRequest {
+    /// Base64 encoded, fully signed wire transaction
+    pub transaction: String,
+    #[serde(default)]
+    pub skip_preflight: bool,
+    pub max_retries: Option<usize>,
+    #[serde(default)]
+    pub commitment: Commitment,
+    pub cluster: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SendTransactionResponse {
+    pub signature: String,
+    pub confirmation_status: Option<Commitment>,
+    pub slot: Option<u64>,
+    pub confirmations: Option<u64>,
+    pub err: Option<serde_json::Value>,
+    /// The blockhash expired before the requested commitment was reached
+    pub expired: bool,
+    /// `rpc.confirmation_timeout_secs` passed before the requested commitment was reached
+    pub timed_out: bool,
+    /// Polling the RPC for the status failed; the transaction was still submitted
+    pub poll_error: Option<String>,
+}
+
+// Transaction simulation types
+#[derive(Deserialize, ToSchema)]
+pub struct SimulateTransactionRequest {
+    /// Base64 encoded wire transaction, signed or not
+    pub transaction: String,
+    #[serde(default)]
+    pub sig_verify: bool,
+    #[serde(default)]
+    pub replace_recent_blockhash: bool,
+    /// Accounts to report balances for before and after the simulation
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    #[serde(default)]
+    pub commitment: Commitment,
+    pub cluster: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SimulateTransactionResponse {
+    pub slot: u64,
+    pub err: Option<serde_json::Value>,
+    pub decoded_error: Option<DecodedInstructionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    pub return_data: Option<SimulationReturnData>,
+    pub accounts: Vec<SimulatedAccountBalance>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DecodedInstructionError {
+    pub instruction_index: u8,
+    pub program_id: String,
+    pub code: u32,
+    pub name: String,
+    pub message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SimulationReturnData {
+    pub program_id: String,
+    pub data: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SimulatedAccountBalance {
+    pub address: String,
+    pub pre_lamports: Option<u64>,
+    pub post_lamports: Option<u64>,
+    pub pre_token_amount: Option<String>,
+    pub post_token_amount: Option<String>,
+}
+
+// Local ledger simulation types
+#[derive(Deserialize, ToSchema)]
+pub struct LocalSimulationRequest {
+    pub instructions: Vec<InstructionRequest>,
+    /// Pays the transaction fee; a funded payer is used when omitted
+    pub fee_payer: Option<String>,
+    /// Accounts treated as signers wherever they appear
+    #[serde(default)]
+    pub signers: Vec<String>,
+    /// Accounts to seed the ledger with before execution
+    #[serde(default)]
+    pub accounts: Vec<LocalAccountSeed>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LocalAccountSeed {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub owner: Option<String>,
+    /// Base64 encoded account data
+    #[serde(default)]
+    pub data: String,
+    #[serde(default)]
+    pub executable: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LocalSimulationResponse {
+    pub success: bool,
+    pub err: Option<String>,
+    pub decoded_error: Option<DecodedInstructionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: u64,
+    pub accounts: Vec<LocalAccountState>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LocalAccountState {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parsed: Option<ParsedAccountData>,
+}
+
+// Transaction builder types
+#[derive(Deserialize, ToSchema)]
+pub struct TransactionBuildRequest {
+    pub instructions: Vec<InstructionRequest>,
+    pub fee_payer: String,
+    /// Accounts treated as signers wherever they appear
+    #[serde(default)]
+    pub signers: Vec<String>,
+    /// Fetched from the RPC when omitted
+    pub recent_blockhash: Option<String>,
+    #[serde(default)]
+    pub commitment: Commitment,
+    pub cluster: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionBuildResponse {
+    /// Base64 encoded wire transaction with empty signature slots
+    pub transaction: String,
+    /// Base64 encoded message, the bytes each signer must sign
+    pub message: String,
+    pub recent_blockhash: String,
+    pub last_valid_block_height: Option<u64>,
+    /// Fee in lamports, when an RPC endpoint is available
+    pub fee: Option<u64>,
+    pub signers: Vec<String>,
+}
+
+// Account decoding types
+#[derive(Deserialize, ToSchema)]
+pub struct DecodeAccountRequest {
+    /// Program that owns the account, or the sysvar program for sysvars
+    pub owner: String,
+    /// Base64 encoded raw account data
+    pub data: String,
+}
+
+// Calculator types
+#[derive(Deserialize, ToSchema)]
+pub struct RentCalcRequest {
+    /// Raw data length; mutually exclusive with `account`
+    pub data_len: Option<u64>,
+    /// Shortcut name such as mint, token_account, multisig, nonce, stake,
+    /// token_2022_mint or token_2022_account
+    pub account: Option<String>,
+    /// Token-2022 extensions to size for, by name (e.g. TransferFeeConfig)
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Rent parameters; the cluster defaults are used when omitted
+    pub rent: Option<RentConfig>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RentConfig {
+    pub lamports_per_byte_year: u64,
+    pub exemption_threshold: f64,
+    pub burn_percent: u8,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RentCalcResponse {
+    pub data_len: u64,
+    pub lamports: u64,
+    pub rent: RentConfig,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct FeeCalcRequest {
+    /// Base64 encoded legacy or versioned message
+    pub message: String,
+    pub lamports_per_signature: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FeeCalcResponse {
+    /// Transaction signatures plus those checked by precompile instructions
+    pub signatures: u64,
+    pub lamports_per_signature: u64,
+    pub base_fee: u64,
+    pub compute_unit_limit: u32,
+    pub compute_unit_price: u64,
+    pub prioritization_fee: u64,
+    pub total_fee: u64,
+}
+
+// Batch types
+#[derive(Deserialize, ToSchema)]
+pub struct BatchRequest {
+    pub operations: Vec<BatchOperation>,
+    /// Fail the whole batch without results if any operation fails
+    #[serde(default)]
+    pub atomic: bool,
+    /// Operations run at once; at most and by default 16, or the lowest `max_concurrent`
+    /// of the routes called
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchOperation {
+    /// Route template, e.g. `/send/sol` or `/account/:pubkey`
+    pub route: String,
+    /// The route's request body; `pubkey` and query parameters for account and cluster
+    /// reads
+    #[serde(default)]
+    pub body: serde_json::Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchResponse {
+    /// One per operation, in request order
+    pub results: Vec<BatchResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchResult {
+    pub route: String,
+    pub success: bool,
+    /// HTTP status the route answered with
+    pub status: u16,
+    /// The route's response envelope
+    pub response: serde_json::Value,
+}
+
+// JSON-RPC types
+/// A JSON-RPC 2.0 call; one without an `id` is a notification and gets no response
+#[derive(Deserialize, ToSchema)]
+pub struct JsonRpcRequest {
+    /// Must be `"2.0"`
+    pub jsonrpc: String,
+    /// Handler name of the operation, e.g. `send_sol`
+    pub method: String,
+    /// The operation's request body; `pubkey` and query parameters for account and
+    /// cluster reads
+    #[serde(default)]
+    pub params: serde_json::Value,
+    /// String, number or null
+    #[serde(default, deserialize_with = "present")]
+    pub id: Option<serde_json::Value>,
+}
+
+/// Tells an explicit `"id": null` apart from a missing `id`
+fn present<'de, D>(deserializer: D) -> Result<Option<serde_json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    serde_json::Value::deserialize(deserializer).map(Some)
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JsonRpcResponse {
+    pub jsonrpc: String,
+    /// The operation's `data`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+    pub id: serde_json::Value,
+}
+
+impl JsonRpcResponse {
+    pub fn success(id: serde_json::Value, result: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    pub fn error(id: serde_json::Value, error: JsonRpcError) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            result: None,
+            error: Some(error),
+            id,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JsonRpcError {
+    pub code: i32,
+    pub message: String,
+    /// The operation's error envelope with its HTTP `status`, when it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+// Shared secret input
+/// Secret key material from a request; its `Debug` output is redacted so it never reaches logs,
+/// and it is wiped from memory when dropped
+#[derive(Deserialize, ToSchema)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+// Shared amount input
+/// A base-unit amount as a JSON number (up to 2^53 - 1) or a string (full u64 range)
+#[derive(Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum AmountInput {
+    Number(u64),
+    Text(String),
+}
+
 // Shared account metadata
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct AccountMetaResponse {
     pub pubkey: String,
     pub is_signer: bool,
     pub is_writable: bool,
-}
\ No newline at end of file
+}
+
+// Shared instruction input
+/// An instruction in the shape returned by the instruction builders
+#[derive(Deserialize, ToSchema)]
+pub struct InstructionRequest {
+    pub program_id: String,
+    pub accounts: Vec<AccountMetaRequest>,
+    pub instruction_data: String,
+}
+
+/// Account metadata as a bare address (writable) or with explicit flags
+#[derive(Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum AccountMetaRequest {
+    Address(String),
+    Meta {
+        pubkey: String,
+        #[serde(default, alias = "isSigner")]
+        is_signer: bool,
+        #[serde(default = "default_true", alias = "isWritable")]
+        is_writable: bool,
+    },
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "4xQy9kZ7secretkeymaterial";
+
+    #[test]
+    fn debug_output_hides_secrets() {
+        let sign: MessageSignRequest =
+            serde_json::from_value(serde_json::json!({ "message": "hi", "secret": KEY })).unwrap();
+        let instruction: Ed25519InstructionRequest = serde_json::from_value(
+            serde_json::json!({ "signatures": [{ "message": "hi", "secret": KEY }] }),
+        )
+        .unwrap();
+        let eth: EthAddressRequest =
+            serde_json::from_value(serde_json::json!({ "secret": KEY })).unwrap();
+
+        for debug in [format!("{:?}", sign), format!("{:?}", instruction), format!("{:#?}", eth)] {
+            assert!(!debug.contains(KEY), "{}", debug);
+            assert!(debug.contains("[REDACTED]"), "{}", debug);
+        }
+        assert_eq!(sign.secret.expose(), KEY);
+    }
+}
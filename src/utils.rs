@@ -1,86 +1,404 @@
-use solana_sdk::{pubkey::Pubkey, signature::Keypair};
-use crate::error::AppError;
-use ed25519_dalek::Keypair as DalekKeypair;
+use base64::Engine;
+use std::collections::HashSet;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    transaction::VersionedTransaction,
+};
+use crate::{
+    error::{AppError, ErrorCode},
+    state::AppState,
+    types::{AccountMetaRequest, AmountInput, InstructionRequest},
+};
 
 /// Parse a base58 encoded public key string
 pub fn parse_pubkey(pubkey_str: &str, field_name: &str) -> Result<Pubkey, AppError> {
     if pubkey_str.trim().is_empty() {
-        return Err(AppError::BadRequest(format!(
-            "Missing required field: {}",
-            field_name
-        )));
+        return Err(AppError::missing(field_name));
     }
 
-    let bytes = bs58::decode(pubkey_str)
-        .into_vec()
-        .map_err(|_| AppError::BadRequest(format!("Invalid base58 for {}", field_name)))?;
+    let bytes = bs58::decode(pubkey_str).into_vec().map_err(|_| {
+        AppError::invalid(
+            ErrorCode::InvalidBase58,
+            field_name,
+            format!("Invalid base58 for {}", field_name),
+        )
+    })?;
 
     if bytes.len() != 32 {
-        return Err(AppError::BadRequest(format!(
-            "Invalid {} pubkey length: expected 32 bytes, got {}",
+        return Err(AppError::invalid(
+            ErrorCode::InvalidPubkey,
             field_name,
-            bytes.len()
-        )));
+            format!(
+                "Invalid {} pubkey length: expected 32 bytes, got {}",
+                field_name,
+                bytes.len()
+            ),
+        ));
     }
 
-    Pubkey::try_from(bytes.as_slice())
-        .map_err(|_| AppError::BadRequest(format!("Invalid {} pubkey", field_name)))
+    Pubkey::try_from(bytes.as_slice()).map_err(|_| {
+        AppError::invalid(
+            ErrorCode::InvalidPubkey,
+            field_name,
+            format!("Invalid {} pubkey", field_name),
+        )
+    })
 }
 
 /// Parse a base58 encoded secret key string into a Keypair
 pub fn parse_secret_key(secret_str: &str) -> Result<Keypair, AppError> {
     if secret_str.trim().is_empty() {
-        return Err(AppError::BadRequest(
-            "Missing required field: secret".to_string(),
-        ));
+        return Err(AppError::missing("secret"));
     }
 
-    let bytes = bs58::decode(secret_str)
-        .into_vec()
-        .map_err(|_| AppError::BadRequest("Invalid base58 for secret".to_string()))?;
+    let invalid = |message: &str| AppError::invalid(ErrorCode::InvalidSecretKey, "secret", message);
+
+    let bytes = bs58::decode(secret_str).into_vec().map_err(|_| {
+        AppError::invalid(ErrorCode::InvalidBase58, "secret", "Invalid base58 for secret")
+    })?;
 
     if bytes.len() != 64 && bytes.len() != 32 {
-        return Err(AppError::BadRequest(format!(
+        return Err(invalid(&format!(
             "Invalid secret key length: expected 32 or 64 bytes, got {}",
             bytes.len()
         )));
     }
 
     if bytes.len() == 64 {
-        Keypair::from_bytes(&bytes)
-            .map_err(|_| AppError::BadRequest("Invalid secret key bytes".to_string()))
+        Keypair::from_bytes(&bytes).map_err(|_| invalid("Invalid secret key bytes"))
     } else {
         let seed: [u8; 32] = bytes
             .try_into()
-            .map_err(|_| AppError::BadRequest("Invalid secret key seed length".to_string()))?;
+            .map_err(|_| invalid("Invalid secret key seed length"))?;
         // Use Keypair::from_bytes after expanding the seed to a 64-byte array using ed25519_dalek
         let dalek_keypair = ed25519_dalek::Keypair::from_bytes(&seed)
-            .map_err(|_| AppError::BadRequest("Invalid secret key seed bytes".to_string()))?;
+            .map_err(|_| invalid("Invalid secret key seed bytes"))?;
         Keypair::from_bytes(&dalek_keypair.to_bytes())
-            .map_err(|_| AppError::BadRequest("Invalid secret key seed".to_string()))
+            .map_err(|_| invalid("Invalid secret key seed"))
+    }
+}
+
+/// Parse a base64 encoded ed25519 signature string
+pub fn parse_signature(signature_str: &str) -> Result<Signature, AppError> {
+    let bytes = decode_base64(signature_str, "signature")?;
+
+    if bytes.len() != 64 {
+        return Err(AppError::invalid(
+            ErrorCode::InvalidSignature,
+            "signature",
+            format!("Invalid signature length: expected 64 bytes, got {}", bytes.len()),
+        ));
+    }
+
+    Signature::try_from(bytes.as_slice()).map_err(|_| {
+        AppError::invalid(ErrorCode::InvalidSignature, "signature", "Invalid signature bytes")
+    })
+}
+
+/// Parse a base64 encoded wire transaction
+pub fn parse_transaction(transaction_str: &str) -> Result<VersionedTransaction, AppError> {
+    validate_not_empty(transaction_str, "transaction")?;
+
+    let bytes = decode_base64(transaction_str, "transaction")?;
+    let invalid = |message: String| {
+        AppError::invalid(ErrorCode::InvalidTransaction, "transaction", message)
+    };
+
+    let transaction: VersionedTransaction = bincode::deserialize(&bytes)
+        .map_err(|_| invalid("Invalid transaction bytes".to_string()))?;
+
+    transaction
+        .sanitize()
+        .map_err(|e| invalid(format!("Invalid transaction: {}", e)))?;
+
+    Ok(transaction)
+}
+
+/// Parse a base64 encoded legacy or versioned message
+pub fn parse_message(message_str: &str) -> Result<VersionedMessage, AppError> {
+    validate_not_empty(message_str, "message")?;
+
+    let bytes = decode_base64(message_str, "message")?;
+    let invalid =
+        |message: String| AppError::invalid(ErrorCode::InvalidMessage, "message", message);
+
+    let message: VersionedMessage = bincode::deserialize(&bytes)
+        .map_err(|_| invalid("Invalid message bytes".to_string()))?;
+
+    message
+        .sanitize()
+        .map_err(|e| invalid(format!("Invalid message: {}", e)))?;
+
+    Ok(message)
+}
+
+/// Parse an instruction in builder response shape, flagging the given signers
+pub fn parse_instruction(
+    instruction: &InstructionRequest,
+    signers: &HashSet<Pubkey>,
+) -> Result<Instruction, AppError> {
+    let program_id = parse_pubkey(&instruction.program_id, "program_id")?;
+
+    let accounts = instruction
+        .accounts
+        .iter()
+        .enumerate()
+        .map(|(index, meta)| {
+            let (pubkey, is_signer, is_writable) = match meta {
+                AccountMetaRequest::Address(pubkey) => (pubkey, false, true),
+                AccountMetaRequest::Meta {
+                    pubkey,
+                    is_signer,
+                    is_writable,
+                } => (pubkey, *is_signer, *is_writable),
+            };
+            let pubkey = parse_pubkey(pubkey, &format!("accounts[{}]", index))?;
+            Ok(AccountMeta {
+                pubkey,
+                is_signer: is_signer || signers.contains(&pubkey),
+                is_writable,
+            })
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    let data = decode_base64(&instruction.instruction_data, "instruction_data")?;
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Parse a hex encoded string (optionally 0x prefixed) of an exact byte length
+pub fn parse_hex(value: &str, field_name: &str, expected_len: usize) -> Result<Vec<u8>, AppError> {
+    validate_not_empty(value, field_name)?;
+
+    let bytes = decode_hex(value, field_name)?;
+
+    if bytes.len() != expected_len {
+        return Err(AppError::invalid(
+            ErrorCode::InvalidLength,
+            field_name,
+            format!(
+                "Invalid {} length: expected {} bytes, got {}",
+                field_name,
+                expected_len,
+                bytes.len()
+            ),
+        ));
+    }
+
+    Ok(bytes)
+}
+
+/// Parse a hex encoded secp256k1 secret key
+pub fn parse_secp256k1_secret_key(secret_str: &str) -> Result<libsecp256k1::SecretKey, AppError> {
+    let bytes = parse_hex(secret_str, "secret", 32)?;
+    libsecp256k1::SecretKey::parse_slice(&bytes).map_err(|_| {
+        AppError::invalid(ErrorCode::InvalidSecretKey, "secret", "Invalid secp256k1 secret key")
+    })
+}
+
+/// Parse a hex encoded secp256k1 public key in compressed, raw or uncompressed form
+pub fn parse_secp256k1_public_key(pubkey_str: &str) -> Result<libsecp256k1::PublicKey, AppError> {
+    validate_not_empty(pubkey_str, "public_key")?;
+
+    let bytes = decode_hex(pubkey_str, "public_key")?;
+
+    if ![33, 64, 65].contains(&bytes.len()) {
+        return Err(AppError::invalid(
+            ErrorCode::InvalidLength,
+            "public_key",
+            format!(
+                "Invalid public_key length: expected 33, 64 or 65 bytes, got {}",
+                bytes.len()
+            ),
+        ));
     }
+
+    libsecp256k1::PublicKey::parse_slice(&bytes, None).map_err(|_| {
+        AppError::invalid(
+            ErrorCode::InvalidPublicKey,
+            "public_key",
+            "Invalid secp256k1 public key",
+        )
+    })
+}
+
+/// Parse a hex encoded 20-byte Ethereum address
+pub fn parse_eth_address(address_str: &str) -> Result<[u8; 20], AppError> {
+    let bytes = parse_hex(address_str, "eth_address", 20)?;
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&bytes);
+    Ok(address)
+}
+
+/// Encode an Ethereum address as EIP-55 checksummed hex
+pub fn encode_eth_address(address: &[u8; 20]) -> String {
+    use sha3::{Digest, Keccak256};
+
+    let lower = hex::encode(address);
+    let hash = Keccak256::digest(lower.as_bytes());
+
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    format!("0x{}", checksummed)
 }
 
 /// Validate that an amount is within safe bounds
 pub fn validate_amount(amount: u64, field_name: &str) -> Result<(), AppError> {
     if amount == 0 {
-        return Err(AppError::BadRequest(format!(
-            "{} must be greater than 0",
-            field_name
-        )));
+        return Err(zero_amount(field_name));
     }
 
     if amount > crate::config::MAX_SAFE_INTEGER {
-        return Err(AppError::BadRequest(format!(
-            "{} exceeds max safe integer ({}).",
-            field_name,
-            crate::config::MAX_SAFE_INTEGER
-        )));
+        return Err(AppError::invalid(
+            ErrorCode::AmountOutOfRange,
+            &field_name.to_lowercase(),
+            format!(
+                "{} exceeds max safe integer ({}). Pass it as a string for the full u64 range.",
+                field_name,
+                crate::config::MAX_SAFE_INTEGER
+            ),
+        ));
     }
 
     Ok(())
 }
 
+/// Parse a base-unit amount, allowing the full u64 range when given as a string
+pub fn parse_amount(amount: &AmountInput, field_name: &str) -> Result<u64, AppError> {
+    match amount {
+        AmountInput::Number(value) => {
+            validate_amount(*value, field_name)?;
+            Ok(*value)
+        }
+        AmountInput::Text(text) => {
+            let field = field_name.to_lowercase();
+            if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(AppError::invalid(
+                    ErrorCode::InvalidAmount,
+                    &field,
+                    format!("{} must be a string of decimal digits", field_name),
+                ));
+            }
+            let value = text.parse::<u64>().map_err(|_| {
+                AppError::invalid(
+                    ErrorCode::AmountOutOfRange,
+                    &field,
+                    format!("{} exceeds the u64 range ({})", field_name, u64::MAX),
+                )
+            })?;
+            if value == 0 {
+                return Err(zero_amount(field_name));
+            }
+            Ok(value)
+        }
+    }
+}
+
+/// Convert a decimal string such as "12.345" into base units without rounding
+pub fn parse_ui_amount(ui_amount: &str, decimals: u8, field_name: &str) -> Result<u64, AppError> {
+    let invalid = || {
+        AppError::invalid(
+            ErrorCode::InvalidAmount,
+            field_name,
+            format!("{} must be a decimal number such as \"12.345\"", field_name),
+        )
+    };
+
+    let (whole, fraction) = ui_amount.split_once('.').unwrap_or((ui_amount, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    // Trailing zeros carry no precision, so "1.50" is fine with 1 decimal
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(AppError::invalid(
+            ErrorCode::AmountPrecision,
+            field_name,
+            format!("{} has more than {} decimal places", field_name, decimals),
+        ));
+    }
+
+    let out_of_range = || {
+        AppError::invalid(
+            ErrorCode::AmountOutOfRange,
+            field_name,
+            format!("{} exceeds the u64 range in base units", field_name),
+        )
+    };
+
+    // Shift the decimal point by writing out the base-unit digits, which works for any
+    // number of decimals; only the significant digits have to fit in a u64
+    let mut digits = String::with_capacity(whole.len() + decimals as usize);
+    digits.push_str(whole);
+    digits.push_str(fraction);
+    digits.extend(std::iter::repeat_n('0', decimals as usize - fraction.len()));
+    let digits = digits.trim_start_matches('0');
+    let value = if digits.is_empty() {
+        0
+    } else {
+        digits.parse::<u64>().map_err(|_| out_of_range())?
+    };
+
+    if value == 0 {
+        return Err(zero_amount(field_name));
+    }
+
+    Ok(value)
+}
+
+/// Resolve a token amount from either base units or a UI amount and the mint decimals
+pub async fn resolve_token_amount(
+    state: &AppState,
+    mint: &Pubkey,
+    amount: Option<&AmountInput>,
+    ui_amount: Option<&str>,
+    decimals: Option<u8>,
+    cluster: Option<&str>,
+) -> Result<u64, AppError> {
+    match (amount, ui_amount) {
+        (Some(amount), None) => parse_amount(amount, "Amount"),
+        (None, Some(ui_amount)) => {
+            let decimals = match decimals {
+                Some(decimals) => decimals,
+                None => state.rpc.endpoint(cluster)?.mint_decimals(mint).await?,
+            };
+            parse_ui_amount(ui_amount, decimals, "ui_amount")
+        }
+        (Some(_), Some(_)) => Err(AppError::invalid(
+            ErrorCode::ConflictingFields,
+            "ui_amount",
+            "Provide either amount or ui_amount, not both",
+        )),
+        (None, None) => Err(AppError::invalid(
+            ErrorCode::MissingField,
+            "amount",
+            "Missing required field: amount or ui_amount",
+        )),
+    }
+}
+
 /// Validate that two public keys are different
 pub fn validate_different_pubkeys(
     pubkey1: &Pubkey,
@@ -89,11 +407,14 @@ pub fn validate_different_pubkeys(
     field2_name: &str,
 ) -> Result<(), AppError> {
     if pubkey1 == pubkey2 {
-        return Err(AppError::BadRequest(format!(
-            "{} and {} cannot be the same public key.",
-            field1_name,
-            field2_name
-        )));
+        return Err(AppError::invalid(
+            ErrorCode::SameAccount,
+            &field2_name.to_lowercase(),
+            format!(
+                "{} and {} cannot be the same public key.",
+                field1_name, field2_name
+            ),
+        ));
     }
     Ok(())
 }
@@ -101,10 +422,162 @@ pub fn validate_different_pubkeys(
 /// Validate that a string field is not empty
 pub fn validate_not_empty(value: &str, field_name: &str) -> Result<(), AppError> {
     if value.trim().is_empty() {
-        return Err(AppError::BadRequest(format!(
-            "Missing required field: {}",
-            field_name
-        )));
+        return Err(AppError::missing(field_name));
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Decode a standard base64 field
+pub fn decode_base64(value: &str, field_name: &str) -> Result<Vec<u8>, AppError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .map_err(|_| {
+            AppError::invalid(
+                ErrorCode::InvalidBase64,
+                field_name,
+                format!("Invalid base64 for {}", field_name),
+            )
+        })
+}
+
+/// Decode a hex field, accepting an optional 0x prefix
+fn decode_hex(value: &str, field_name: &str) -> Result<Vec<u8>, AppError> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value)).map_err(|_| {
+        AppError::invalid(
+            ErrorCode::InvalidHex,
+            field_name,
+            format!("Invalid hex for {}", field_name),
+        )
+    })
+}
+
+fn zero_amount(field_name: &str) -> AppError {
+    AppError::invalid(
+        ErrorCode::InvalidAmount,
+        &field_name.to_lowercase(),
+        format!("{} must be greater than 0", field_name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Config, MAX_SAFE_INTEGER};
+    use super::*;
+
+    #[test]
+    fn eth_addresses_use_the_eip55_checksum() {
+        // Test vectors from EIP-55
+        for expected in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address = parse_eth_address(&expected.to_lowercase()).unwrap();
+            assert_eq!(encode_eth_address(&address), expected);
+        }
+    }
+
+    fn ui_error(ui_amount: &str, decimals: u8) -> ErrorCode {
+        parse_ui_amount(ui_amount, decimals, "ui_amount").unwrap_err().code()
+    }
+
+    #[test]
+    fn ui_amount_scales_by_decimals() {
+        assert_eq!(parse_ui_amount("12.345", 6, "ui_amount").unwrap(), 12_345_000);
+        assert_eq!(parse_ui_amount("1.50", 1, "ui_amount").unwrap(), 15);
+        assert_eq!(parse_ui_amount("007", 0, "ui_amount").unwrap(), 7);
+    }
+
+    #[test]
+    fn ui_amount_rejects_extra_precision() {
+        assert_eq!(ui_error("1.2345", 3), ErrorCode::AmountPrecision);
+        assert_eq!(ui_error("1.5", 0), ErrorCode::AmountPrecision);
+        assert_eq!(parse_ui_amount("1.000", 0, "ui_amount").unwrap(), 1);
+    }
+
+    #[test]
+    fn ui_amount_handles_extreme_decimals() {
+        let smallest = format!("0.{}1", "0".repeat(254));
+        assert_eq!(parse_ui_amount(&smallest, 255, "ui_amount").unwrap(), 1);
+        assert_eq!(ui_error("1", 255), ErrorCode::AmountOutOfRange);
+        assert_eq!(ui_error(&format!("0.{}1", "0".repeat(255)), 255), ErrorCode::AmountPrecision);
+    }
+
+    #[test]
+    fn ui_amount_covers_the_u64_range() {
+        assert_eq!(parse_ui_amount("18446744073709551615", 0, "ui_amount").unwrap(), u64::MAX);
+        assert_eq!(parse_ui_amount("18446744073.709551615", 9, "ui_amount").unwrap(), u64::MAX);
+        assert_eq!(ui_error("18446744073709551616", 0), ErrorCode::AmountOutOfRange);
+        assert_eq!(ui_error("18446744073.709551616", 9), ErrorCode::AmountOutOfRange);
+    }
+
+    #[test]
+    fn ui_amount_accepts_bare_dots_around_digits() {
+        assert_eq!(parse_ui_amount(".5", 1, "ui_amount").unwrap(), 5);
+        assert_eq!(parse_ui_amount("5.", 1, "ui_amount").unwrap(), 50);
+        assert_eq!(ui_error(".", 9), ErrorCode::InvalidAmount);
+        assert_eq!(ui_error("", 9), ErrorCode::InvalidAmount);
+        assert_eq!(ui_error("1.2.3", 9), ErrorCode::InvalidAmount);
+    }
+
+    #[test]
+    fn ui_amount_rejects_signs_exponents_and_zero() {
+        for input in ["-1", "+1", "1e3", "1E3", " 1", "1_000", "0x10", "١"] {
+            assert_eq!(ui_error(input, 9), ErrorCode::InvalidAmount, "{:?}", input);
+        }
+        let Err(AppError::Invalid(error)) = parse_ui_amount("0.000", 3, "ui_amount") else {
+            panic!("expected a field error");
+        };
+        assert_eq!(error.field.as_deref(), Some("ui_amount"));
+        assert_eq!(error.message, "ui_amount must be greater than 0");
+    }
+
+    #[test]
+    fn amount_numbers_stop_at_the_safe_integer_limit() {
+        let amount = |value| parse_amount(&AmountInput::Number(value), "Amount");
+        assert_eq!(amount(MAX_SAFE_INTEGER).unwrap(), MAX_SAFE_INTEGER);
+        assert_eq!(amount(MAX_SAFE_INTEGER + 1).unwrap_err().code(), ErrorCode::AmountOutOfRange);
+        assert_eq!(amount(0).unwrap_err().code(), ErrorCode::InvalidAmount);
+    }
+
+    #[test]
+    fn amount_strings_cover_the_u64_range() {
+        let amount = |text: &str| parse_amount(&AmountInput::Text(text.to_string()), "Amount");
+        assert_eq!(amount("18446744073709551615").unwrap(), u64::MAX);
+        assert_eq!(amount("18446744073709551616").unwrap_err().code(), ErrorCode::AmountOutOfRange);
+        for input in ["", "0", "-1", "1.0", "1e3"] {
+            assert_eq!(amount(input).unwrap_err().code(), ErrorCode::InvalidAmount, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn amount_input_takes_numbers_and_strings() {
+        let number: AmountInput = serde_json::from_str("42").unwrap();
+        let text: AmountInput = serde_json::from_str("\"42\"").unwrap();
+        assert!(matches!(number, AmountInput::Number(42)));
+        assert!(matches!(text, AmountInput::Text(ref text) if text == "42"));
+        assert!(serde_json::from_str::<AmountInput>("-1").is_err());
+        assert!(serde_json::from_str::<AmountInput>("1.5").is_err());
+    }
+
+    #[tokio::test]
+    async fn token_amount_takes_exactly_one_form() {
+        let state = AppState::new(Config::default(), None);
+        let mint = Pubkey::new_unique();
+        let number = AmountInput::Number(1_500);
+        let text = AmountInput::Text("1500".to_string());
+        let resolve = |amount, ui_amount| {
+            resolve_token_amount(&state, &mint, amount, ui_amount, Some(3), None)
+        };
+
+        assert_eq!(resolve(Some(&number), None).await.unwrap(), 1_500);
+        assert_eq!(resolve(Some(&text), None).await.unwrap(), 1_500);
+        assert_eq!(resolve(None, Some("1.5")).await.unwrap(), 1_500);
+
+        let both = resolve(Some(&number), Some("1.5")).await.unwrap_err();
+        assert_eq!(both.code(), ErrorCode::ConflictingFields);
+        let neither = resolve(None, None).await.unwrap_err();
+        assert_eq!(neither.code(), ErrorCode::MissingField);
+    }
+}
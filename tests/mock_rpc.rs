@@ -0,0 +1,298 @@
+//! RPC-backed routes served by the API binary against the bundled mock RPC server, which
+//! replays `fixtures/rpc`. Each test starts its own pair of processes, so the mock's
+//! `sequence` cursors are not shared between tests.
+
+use std::{
+    net::TcpListener,
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+use base64::Engine;
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{keypair::keypair_from_seed, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+/// USDC mint, answered by `getAccountInfo.json`
+const MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+/// Blockhash returned by `getLatestBlockhash.json`
+const BLOCKHASH: &str = "EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N";
+/// Signature returned by `sendTransaction.json`
+const SIGNATURE: &str =
+    "VxZZXaBkMVwhdcRWefrcN5mtQD5oE459eEkmakkG4s37P6nPRhnEN939ui9eeXzLNebUQNWQzJ9GXwuqFLXL9Mj";
+
+/// Transfers keyed in `sendTransaction.json` whose signatures never confirm, or whose
+/// status lookups fail
+const UNCONFIRMED_LAMPORTS: u64 = 1;
+const UNTRACKABLE_LAMPORTS: u64 = 2;
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The API and a mock RPC server it points at, both stopped on drop
+struct Servers {
+    api: Child,
+    mock: Child,
+    base_url: String,
+    client: Client,
+}
+
+impl Servers {
+    async fn start() -> Self {
+        Self::with_confirmation_timeout(10).await
+    }
+
+    async fn with_confirmation_timeout(secs: u64) -> Self {
+        let mock_port = free_port();
+        let mock = Command::new(env!("CARGO_BIN_EXE_mock_rpc"))
+            .env_clear()
+            .env("MOCK_RPC_PORT", mock_port.to_string())
+            .env(
+                "MOCK_RPC_FIXTURES",
+                concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/rpc"),
+            )
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("failed to start mock_rpc");
+
+        let api_port = free_port();
+        let api = Command::new(env!("CARGO_BIN_EXE_solana-rust-api"))
+            .env_clear()
+            .args(["--bind-address", "127.0.0.1", "--port", &api_port.to_string()])
+            .args(["--rpc-url", &format!("http://127.0.0.1:{}", mock_port)])
+            .args(["--confirmation-timeout-secs", &secs.to_string()])
+            .args(["--log-filter", "error"])
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("failed to start solana-rust-api");
+
+        let servers = Self {
+            api,
+            mock,
+            base_url: format!("http://127.0.0.1:{}", api_port),
+            client: Client::new(),
+        };
+        servers.wait_until_ready().await;
+        servers
+    }
+
+    /// Wait for readiness, which only passes once the API can reach the mock
+    async fn wait_until_ready(&self) {
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            let url = format!("{}/health/ready", self.base_url);
+            if let Ok(response) = self.client.get(url).send().await {
+                if response.status() == StatusCode::OK {
+                    return;
+                }
+            }
+            assert!(Instant::now() < deadline, "servers did not become ready");
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    async fn get(&self, path: &str) -> (StatusCode, Value) {
+        let response = self.client.get(format!("{}{}", self.base_url, path)).send().await;
+        read(response.unwrap()).await
+    }
+
+    async fn post(&self, path: &str, body: Value) -> (StatusCode, Value) {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self.client.post(url).json(&body).send().await;
+        read(response.unwrap()).await
+    }
+}
+
+impl Drop for Servers {
+    fn drop(&mut self) {
+        for child in [&mut self.api, &mut self.mock] {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+async fn read(response: reqwest::Response) -> (StatusCode, Value) {
+    let status = response.status();
+    (status, response.json().await.unwrap())
+}
+
+fn free_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+fn transfer(payer: &Keypair) -> Transaction {
+    let instruction = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1_000);
+    let blockhash = BLOCKHASH.parse::<Hash>().unwrap();
+    Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer], blockhash)
+}
+
+/// Same bytes on every run, so fixtures can match on the encoded transaction
+fn fixed_transfer(lamports: u64) -> Transaction {
+    let payer = keypair_from_seed(&[7; 32]).unwrap();
+    let recipient = Pubkey::new_from_array([9; 32]);
+    let instruction = system_instruction::transfer(&payer.pubkey(), &recipient, lamports);
+    let blockhash = BLOCKHASH.parse::<Hash>().unwrap();
+    Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[&payer], blockhash)
+}
+
+fn encode(transaction: &Transaction) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bincode::serialize(transaction).unwrap())
+}
+
+#[tokio::test]
+async fn readiness_reports_the_rpc_check() {
+    let servers = Servers::start().await;
+
+    let (status, body) = servers.get("/health/ready").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "ok");
+    let checks = body["data"]["checks"].as_array().unwrap();
+    assert_eq!(checks.len(), 1);
+    assert!(checks[0]["error"].is_null(), "{}", body);
+}
+
+#[tokio::test]
+async fn cluster_info_comes_from_the_rpc() {
+    let servers = Servers::start().await;
+
+    let (status, body) = servers.get("/cluster/info").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["epoch"], 27);
+    assert_eq!(body["data"]["slot"], 166598);
+    assert_eq!(body["data"]["block_height"], 166500);
+    assert_eq!(body["data"]["solana_core"], "1.18.26");
+}
+
+#[tokio::test]
+async fn accounts_are_fetched_and_decoded() {
+    let servers = Servers::start().await;
+
+    let (status, body) = servers.get(&format!("/account/{}", MINT)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["owner"], "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+    assert_eq!(body["data"]["lamports"], 388127047454u64);
+
+    // Any other address falls through to the fixture's `value: null` case
+    let missing = Pubkey::new_unique();
+    let (status, body) = servers.get(&format!("/account/{}", missing)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+    assert_eq!(body["success"], false);
+}
+
+#[tokio::test]
+async fn malformed_rpc_accounts_are_gateway_errors() {
+    let servers = Servers::start().await;
+
+    // `getAccountInfo.json` reports an owner that is not a public key for this address
+    let account = Pubkey::new_from_array([5; 32]);
+    let (status, body) = servers.get(&format!("/account/{}", account)).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY, "{}", body);
+    assert_eq!(body["code"], "RPC_ERROR");
+    assert!(body["field"].is_null());
+}
+
+#[tokio::test]
+async fn built_transactions_use_the_rpc_blockhash_and_fee() {
+    let servers = Servers::start().await;
+    let payer = Pubkey::new_unique();
+    let body = json!({
+        "fee_payer": payer.to_string(),
+        "instructions": [{
+            "program_id": "11111111111111111111111111111111",
+            "accounts": [
+                { "pubkey": payer.to_string(), "is_signer": true, "is_writable": true },
+                Pubkey::new_unique().to_string(),
+            ],
+            // System transfer of 1000 lamports
+            "instruction_data": "AgAAAOgDAAAAAAAA",
+        }],
+    });
+
+    let (status, body) = servers.post("/transaction/build", body).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["recent_blockhash"], BLOCKHASH);
+    assert_eq!(body["data"]["last_valid_block_height"], 166750);
+    assert_eq!(body["data"]["fee"], 5000);
+    assert_eq!(body["data"]["signers"], json!([payer.to_string()]));
+}
+
+#[tokio::test]
+async fn sent_transactions_wait_for_confirmation() {
+    let servers = Servers::start().await;
+    let transaction = encode(&transfer(&Keypair::new()));
+
+    // The fixture reports the signature unknown, then processed, then confirmed
+    let (status, body) = servers
+        .post("/transaction/send", json!({ "transaction": transaction }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["signature"], SIGNATURE);
+    assert_eq!(body["data"]["confirmation_status"], "confirmed");
+    assert_eq!(body["data"]["slot"], 166599);
+    assert_eq!(body["data"]["expired"], false);
+    assert_eq!(body["data"]["timed_out"], false);
+    assert!(body["data"]["poll_error"].is_null());
+}
+
+#[tokio::test]
+async fn sends_that_never_confirm_still_return_the_signature() {
+    let servers = Servers::with_confirmation_timeout(1).await;
+    let transaction = fixed_transfer(UNCONFIRMED_LAMPORTS);
+
+    let body = json!({ "transaction": encode(&transaction) });
+    let (status, body) = servers.post("/transaction/send", body).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["signature"], transaction.signatures[0].to_string());
+    assert_eq!(body["data"]["timed_out"], true);
+    assert!(body["data"]["confirmation_status"].is_null());
+    assert!(body["data"]["poll_error"].is_null());
+}
+
+#[tokio::test]
+async fn sends_whose_status_cannot_be_polled_still_return_the_signature() {
+    let servers = Servers::start().await;
+    let transaction = fixed_transfer(UNTRACKABLE_LAMPORTS);
+
+    let body = json!({ "transaction": encode(&transaction) });
+    let (status, body) = servers.post("/transaction/send", body).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["signature"], transaction.signatures[0].to_string());
+    assert_eq!(body["data"]["timed_out"], false);
+    let poll_error = body["data"]["poll_error"].as_str().unwrap();
+    assert!(poll_error.contains("Node is unhealthy"), "{}", poll_error);
+}
+
+#[tokio::test]
+async fn simulations_report_logs_and_decoded_errors() {
+    let servers = Servers::start().await;
+    let transaction = encode(&transfer(&Keypair::new()));
+
+    let (status, body) = servers
+        .post("/transaction/simulate", json!({ "transaction": transaction }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["data"]["err"].is_null(), "{}", body);
+    assert_eq!(body["data"]["units_consumed"], 150);
+
+    // The transaction the fixture's failing case is keyed on
+    let fixture: Value = serde_json::from_str(include_str!(
+        "../fixtures/rpc/simulateTransaction.json"
+    ))
+    .unwrap();
+    let failing = fixture[0]["params"][0].clone();
+    let (status, body) = servers
+        .post("/transaction/simulate", json!({ "transaction": failing }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["data"]["decoded_error"]["instruction_index"], 0);
+    assert_eq!(body["data"]["decoded_error"]["code"], 1);
+    assert_eq!(body["data"]["logs"].as_array().unwrap().len(), 3);
+}
+